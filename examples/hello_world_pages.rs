@@ -7,7 +7,7 @@ extern crate rocket_include_tera;
 use std::collections::HashMap;
 
 use rocket::State;
-use rocket_include_tera::{EtagIfNoneMatch, TeraContextManager, TeraResponse};
+use rocket_include_tera::{EtagIfNoneMatch, TeraAssets, TeraContextManager, TeraResponse};
 use serde_json::json;
 
 #[get("/")]
@@ -33,6 +33,19 @@ fn index_disable_minify(
     tera_response!(disable_minify tera_cm, etag_if_none_match, "index", map)
 }
 
+#[get("/async")]
+async fn index_async(
+    tera_cm: &State<TeraContextManager>,
+    etag_if_none_match: EtagIfNoneMatch<'_>,
+) -> TeraResponse {
+    let mut map = HashMap::new();
+
+    map.insert("title", "Title");
+    map.insert("body", "Hello, world!");
+
+    tera_response!(async tera_cm, etag_if_none_match, "index", map)
+}
+
 #[get("/2")]
 fn index_2(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
     tera_response_cache!(cm, etag_if_none_match, "index-2", {
@@ -50,14 +63,25 @@ fn index_2(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch)
 
 #[launch]
 fn rocket() -> _ {
+    let mut assets = TeraAssets::new("/assets");
+
+    tera_assets_initialize!(
+        assets,
+        "css/style.css" => "examples/assets/css/style.css"
+    );
+
     rocket::build()
-        .attach(TeraResponse::fairing(|tera| {
-            tera_resources_initialize!(
-                tera,
-                "index" => "examples/views/index.tera",
-                "index2" => ("examples", "views", "index2.tera")
-            );
-        }))
-        .mount("/", routes![index, index_disable_minify])
+        .attach(
+            TeraResponse::fairing(|tera| {
+                tera_resources_initialize!(
+                    tera,
+                    "index" => "examples/views/index.tera",
+                    "index2" => ("examples", "views", "index2.tera")
+                );
+            })
+            .assets(assets.clone()),
+        )
+        .mount("/", routes![index, index_disable_minify, index_async])
         .mount("/", routes![index_2])
+        .mount("/assets", assets)
 }
@@ -0,0 +1,178 @@
+use std::collections::BTreeSet;
+
+use tera::{
+    ast::{Expr, ExprVal, FunctionCall, Node},
+    Template,
+};
+
+/// Variables which are always available when rendering a template.
+const BUILTIN_VARIABLES: [&str; 3] = ["__tera_context", "loop", "self"];
+
+#[inline]
+pub(crate) fn is_builtin_variable(variable: &str) -> bool {
+    BUILTIN_VARIABLES.contains(&variable)
+}
+
+/// Parse a template and get the root names of the variables which it reads from its context. Only the template itself is read, not the templates which it extends or includes. Variables defined by `set` or `for` are tracked for the whole template instead of by scope, so the result can miss a variable, but never contains one which the template does not read. `Template::new` and `tera::ast` are hidden from the documentation of Tera, so a new version of Tera may break this.
+pub(crate) fn template_variables(name: &str, source: &str) -> Result<BTreeSet<String>, String> {
+    let template = Template::new(name, None, source).map_err(|err| {
+        let mut message = err.to_string();
+
+        let mut source = std::error::Error::source(&err);
+
+        while let Some(err) = source {
+            message.push_str(&format!(": {}", err));
+
+            source = err.source();
+        }
+
+        message
+    })?;
+
+    let mut collector = Collector::default();
+
+    collector.nodes(&template.ast);
+
+    Ok(collector
+        .used
+        .into_iter()
+        .filter(|v| !collector.defined.contains(v) && !collector.optional.contains(v))
+        .collect())
+}
+
+#[derive(Default)]
+struct Collector {
+    used:     BTreeSet<String>,
+    defined:  BTreeSet<String>,
+    optional: BTreeSet<String>,
+}
+
+impl Collector {
+    fn nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::VariableBlock(_, expr) => self.expr(expr),
+            Node::Set(_, set) => {
+                self.defined.insert(set.key.clone());
+                self.expr(&set.value);
+            },
+            Node::FilterSection(_, section, _) => {
+                self.function_call(&section.filter);
+                self.nodes(&section.body);
+            },
+            Node::Block(_, block, _) => self.nodes(&block.body),
+            Node::Forloop(_, forloop, _) => {
+                if let Some(key) = &forloop.key {
+                    self.defined.insert(key.clone());
+                }
+
+                self.defined.insert(forloop.value.clone());
+                self.expr(&forloop.container);
+                self.nodes(&forloop.body);
+
+                if let Some(empty_body) = &forloop.empty_body {
+                    self.nodes(empty_body);
+                }
+            },
+            Node::If(branches, _) => {
+                for (_, expr, body) in &branches.conditions {
+                    self.expr(expr);
+                    self.nodes(body);
+                }
+
+                if let Some((_, body)) = &branches.otherwise {
+                    self.nodes(body);
+                }
+            },
+            // macros can only access their arguments
+            Node::MacroDefinition(..) => (),
+            _ => (),
+        }
+    }
+
+    fn function_call(&mut self, function_call: &FunctionCall) {
+        for arg in function_call.args.values() {
+            self.expr(arg);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.val {
+            // a variable with a default value may be undefined
+            ExprVal::Ident(ident) if expr.has_default_filter() => {
+                self.optional.insert(root_of(ident).to_string());
+            },
+            expr_val => self.expr_val(expr_val),
+        }
+
+        for filter in &expr.filters {
+            self.function_call(filter);
+        }
+    }
+
+    fn expr_val(&mut self, expr_val: &ExprVal) {
+        match expr_val {
+            ExprVal::Ident(ident) => self.ident(ident),
+            ExprVal::Math(math) => {
+                self.expr(&math.lhs);
+                self.expr(&math.rhs);
+            },
+            ExprVal::Logic(logic) => {
+                self.expr(&logic.lhs);
+                self.expr(&logic.rhs);
+            },
+            ExprVal::Test(test) => {
+                // a variable which is tested whether it is defined may be undefined
+                if test.name == "defined" || test.name == "undefined" {
+                    self.optional.insert(root_of(&test.ident).to_string());
+                } else {
+                    self.ident(&test.ident);
+                }
+
+                for arg in &test.args {
+                    self.expr(arg);
+                }
+            },
+            ExprVal::MacroCall(macro_call) => {
+                for arg in macro_call.args.values() {
+                    self.expr(arg);
+                }
+            },
+            ExprVal::FunctionCall(function_call) => self.function_call(function_call),
+            ExprVal::Array(exprs) => {
+                for expr in exprs {
+                    self.expr(expr);
+                }
+            },
+            ExprVal::StringConcat(concat) => {
+                for value in &concat.values {
+                    self.expr_val(value);
+                }
+            },
+            ExprVal::In(expr_in) => {
+                self.expr(&expr_in.lhs);
+                self.expr(&expr_in.rhs);
+            },
+            ExprVal::String(_) | ExprVal::Int(_) | ExprVal::Float(_) | ExprVal::Bool(_) => (),
+        }
+    }
+
+    fn ident(&mut self, ident: &str) {
+        let root = root_of(ident);
+
+        if !root.is_empty() {
+            self.used.insert(root.to_string());
+        }
+    }
+}
+
+/// Get the root name of an identifier, e.g. `user` of `user.name` or `items[0]`.
+#[inline]
+fn root_of(ident: &str) -> &str {
+    ident.split(['.', '[']).next().unwrap_or(ident).trim()
+}
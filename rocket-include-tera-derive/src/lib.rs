@@ -0,0 +1,273 @@
+/*!
+# Derive `TeraTemplate` for Rocket Include Tera
+
+This crate provides the derive macro `TeraTemplate` for the `rocket-include-tera` crate. Use it through the `rocket-include-tera` crate instead of depending on this crate directly.
+*/
+
+mod check;
+
+use std::{env, fs, path::PathBuf};
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput, Fields, LitBool, LitStr};
+
+struct TemplateAttribute {
+    name:       LitStr,
+    path:       Option<LitStr>,
+    minify:     Option<LitBool>,
+    tagged:     Option<syn::Type>,
+    skip_check: bool,
+    provided:   Vec<String>,
+}
+
+fn parse_template_attribute(input: &DeriveInput) -> syn::Result<TemplateAttribute> {
+    let mut name = None;
+    let mut path = None;
+    let mut minify = None;
+    let mut tagged = None;
+    let mut skip_check = false;
+    let mut provided = Vec::new();
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("template")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("path") {
+                path = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("minify") {
+                minify = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("tagged") {
+                tagged = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("skip_check") {
+                skip_check = true;
+            } else if meta.path.is_ident("provided") {
+                meta.parse_nested_meta(|meta| {
+                    match meta.path.get_ident() {
+                        Some(ident) => provided.push(ident.to_string()),
+                        None => return Err(meta.error("expected the name of a variable")),
+                    }
+
+                    Ok(())
+                })?;
+            } else {
+                return Err(meta.error(
+                    "expected `name`, `path`, `minify`, `tagged`, `skip_check` or `provided`",
+                ));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    match name {
+        Some(name) => Ok(TemplateAttribute {
+            name,
+            path,
+            minify,
+            tagged,
+            skip_check,
+            provided,
+        }),
+        None => Err(syn::Error::new(
+            input.ident.span(),
+            "`#[template(name = \"...\")]` is required to derive `TeraTemplate`",
+        )),
+    }
+}
+
+/// Get the names of the fields after they are serialized, or `None` if they cannot be known.
+fn serialized_field_names(input: &DeriveInput) -> syn::Result<Option<Vec<String>>> {
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "`TeraTemplate` can only be derived for structs",
+            ))
+        },
+    };
+
+    let fields = match &data.fields {
+        Fields::Named(fields) => &fields.named,
+        _ => return Ok(None),
+    };
+
+    let mut unknown = false;
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") {
+                unknown = true;
+            }
+
+            if meta.input.peek(syn::Token![=]) {
+                let _: syn::Expr = meta.value()?.parse()?;
+            }
+
+            Ok(())
+        });
+    }
+
+    let mut names = Vec::with_capacity(fields.len());
+
+    for field in fields {
+        let mut name = field.ident.as_ref().unwrap().to_string();
+
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            let _ = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let s: LitStr = meta.value()?.parse()?;
+
+                    name = s.value();
+                } else {
+                    if meta.path.is_ident("flatten") {
+                        unknown = true;
+                    }
+
+                    if meta.input.peek(syn::Token![=]) {
+                        let _: syn::Expr = meta.value()?.parse()?;
+                    }
+                }
+
+                Ok(())
+            });
+        }
+
+        names.push(name.strip_prefix("r#").map(|s| s.to_string()).unwrap_or(name));
+    }
+
+    if unknown {
+        Ok(None)
+    } else {
+        Ok(Some(names))
+    }
+}
+
+fn check_template(
+    input: &DeriveInput,
+    attribute: &TemplateAttribute,
+    path: &LitStr,
+) -> syn::Result<()> {
+    let manifest_dir = env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap_or_default();
+
+    let file_path = manifest_dir.join(path.value());
+
+    let source = fs::read_to_string(&file_path).map_err(|err| {
+        syn::Error::new(path.span(), format!("cannot read `{}`: {}", file_path.display(), err))
+    })?;
+
+    if attribute.skip_check {
+        return Ok(());
+    }
+
+    let field_names = match serialized_field_names(input)? {
+        Some(field_names) => field_names,
+        None => return Ok(()),
+    };
+
+    let variables = check::template_variables(&attribute.name.value(), &source)
+        .map_err(|err| syn::Error::new(path.span(), err))?;
+
+    for variable in variables {
+        if !field_names.contains(&variable)
+            && !attribute.provided.contains(&variable)
+            && !check::is_builtin_variable(&variable)
+        {
+            return Err(syn::Error::new(
+                path.span(),
+                format!(
+                    "the template `{}` uses the variable `{}`, which is not a field of `{}`",
+                    path.value(),
+                    variable,
+                    input.ident
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn derive_input_handler(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let attribute = parse_template_attribute(&input)?;
+
+    let ident = &input.ident;
+    let name = &attribute.name;
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut responder_generics = input.generics.clone();
+    responder_generics.params.insert(0, syn::parse_quote!('o: 'r));
+    responder_generics.params.insert(0, syn::parse_quote!('r));
+
+    let (responder_impl_generics, ..) = responder_generics.split_for_impl();
+
+    let register = match &attribute.path {
+        Some(path) => {
+            check_template(&input, &attribute, path)?;
+
+            quote! {
+                #[inline]
+                fn register(tera: &mut ::rocket_include_tera::TeraRegistry) {
+                    // rebuild when the template changes, so that it can be checked again
+                    const _: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/", #path));
+
+                    ::rocket_include_tera::tera_resources_initialize!(tera, #name => #path);
+                }
+            }
+        },
+        None => quote! {},
+    };
+
+    let minify = match &attribute.minify {
+        Some(minify) => quote! {
+            const MINIFY: bool = #minify;
+        },
+        None => quote! {},
+    };
+
+    let tagged = match &attribute.tagged {
+        Some(tagged) => quote! { #tagged },
+        None => quote! { () },
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::rocket_include_tera::TeraTemplate for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #name;
+
+            #minify
+
+            #register
+        }
+
+        impl #responder_impl_generics ::rocket_include_tera::rocket::response::Responder<'r, 'o> for #ident #ty_generics #where_clause {
+            #[inline]
+            fn respond_to(self, request: &'r ::rocket_include_tera::rocket::request::Request<'_>) -> ::rocket_include_tera::rocket::response::Result<'o> {
+                ::rocket_include_tera::TeraTemplate::respond_template::<#tagged>(&self, request)
+            }
+        }
+    })
+}
+
+/// Implement `TeraTemplate` and `Responder` for a context struct of a template. The `name` of the template is required. If the `path` of the template file (relative to the directory containing the manifest of your package) is given, the template is registered the first time the struct is responded, unless it is already registered in the fairing, and the variables used in the template are checked against the fields of the struct at compile time unless `skip_check` is set. Variables which are provided by the fairing instead of the struct, such as `csp_nonce`, `csrf_token` or `flash`, need to be listed in `provided`. The struct is responded with the `TeraContextManager` of the marker type given by `tagged`, e.g. `tagged = Admin` for a fairing which is `tagged::<Admin>()`, or `()` by default.
+///
+/// The check only reads the template itself, so the variables used by the templates which it extends or includes are not checked. Variables defined by `set` or `for` are known to the whole template instead of only to their scopes, so a field which is missing where such a variable is out of scope is not caught. It parses the template with the AST of Tera, which is not a stable API of Tera.
+///
+/// ```rust,ignore
+/// #[derive(Serialize, TeraTemplate)]
+/// #[template(name = "index", path = "views/index.tera", provided(csp_nonce))]
+/// struct Index {
+///     title: String,
+///     body:  String,
+/// }
+/// ```
+#[proc_macro_derive(TeraTemplate, attributes(template))]
+pub fn tera_template_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match derive_input_handler(input) {
+        Ok(token_stream) => token_stream.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
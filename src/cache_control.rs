@@ -0,0 +1,61 @@
+use std::{
+    borrow::Cow,
+    fmt::{self, Display, Formatter},
+};
+
+/// A policy of the `Cache-Control` header of a `TeraResponse`. Set a default by the `cache_control` method of the fairing of `TeraResponse`, and override it by the `cache_control` method of `TeraResponse`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheControl {
+    /// `no-cache`. Caches must revalidate the response, e.g. with its ETag, before using it.
+    NoCache,
+    /// `no-store`. The response must not be stored at all.
+    NoStore,
+    /// `private, max-age=N`. Only the browser may store the response, for `max_age` seconds.
+    Private { max_age: u64 },
+    /// `public, max-age=N` with optional `s-maxage=N` and `stale-while-revalidate=N`. Shared caches such as CDNs may store the response as well.
+    Public {
+        max_age:                u64,
+        s_maxage:               Option<u64>,
+        stale_while_revalidate: Option<u64>,
+    },
+    /// Any other value of the header.
+    Custom(Cow<'static, str>),
+}
+
+impl CacheControl {
+    /// Create a `Custom` policy.
+    #[inline]
+    pub fn custom<S: Into<Cow<'static, str>>>(value: S) -> CacheControl {
+        CacheControl::Custom(value.into())
+    }
+}
+
+impl Display for CacheControl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheControl::NoCache => f.write_str("no-cache"),
+            CacheControl::NoStore => f.write_str("no-store"),
+            CacheControl::Private {
+                max_age,
+            } => write!(f, "private, max-age={}", max_age),
+            CacheControl::Public {
+                max_age,
+                s_maxage,
+                stale_while_revalidate,
+            } => {
+                write!(f, "public, max-age={}", max_age)?;
+
+                if let Some(s_maxage) = s_maxage {
+                    write!(f, ", s-maxage={}", s_maxage)?;
+                }
+
+                if let Some(stale_while_revalidate) = stale_while_revalidate {
+                    write!(f, ", stale-while-revalidate={}", stale_while_revalidate)?;
+                }
+
+                Ok(())
+            },
+            CacheControl::Custom(value) => f.write_str(value),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::{borrow::Cow, marker::PhantomData};
+
+use rocket::{
+    catcher::{self, Catcher, Handler},
+    http::Status,
+    request::Request,
+    response::Responder,
+};
+use serde_json::json;
+
+use crate::{CacheControl, EtagIfNoneMatch, TeraContextManager};
+
+/// Parse the status code of `tera_catchers!` from the tokens of a string literal like `"404"` or an integer literal like `404`, which fails at compile time if it is not a code of errors.
+#[doc(hidden)]
+pub const fn parse_catcher_code(code: &str) -> u16 {
+    let bytes = code.as_bytes();
+
+    let (mut i, end) = if bytes.len() >= 2 && bytes[0] == b'"' && bytes[bytes.len() - 1] == b'"' {
+        (1, bytes.len() - 1)
+    } else {
+        (0, bytes.len())
+    };
+
+    if i == end {
+        panic!("The status code of a catcher must be a number.");
+    }
+
+    let mut value: u32 = 0;
+
+    while i < end {
+        let b = bytes[i];
+
+        if !b.is_ascii_digit() || value >= 1000 {
+            panic!("The status code of a catcher must be a number.");
+        }
+
+        value = value * 10 + (b - b'0') as u32;
+
+        i += 1;
+    }
+
+    if value < 400 || value >= 600 {
+        panic!("The status code of a catcher must be in the range 400 to 599.");
+    }
+
+    value as u16
+}
+
+/// A catcher handler which renders the error page of a status with a template of a `TeraContextManager`. The template gets `status` (e.g. `404`), `reason` (e.g. `Not Found`) and `path` of the request, together with the values provided by the fairing, such as `csp_nonce`. If the `TeraContextManager` is not managed, or the template cannot be rendered, a plain text page of the status is responded instead, so that the error is never turned into a panic. The type parameter is the marker of the `TeraContextManager`. Use `tera_catchers!` to create catchers for the default `TeraContextManager`.
+///
+/// ```rust,ignore
+/// rocket::build().register("/", vec![TeraCatcher::new(404, "errors/404").into()])
+/// ```
+pub struct TeraCatcher<T = ()> {
+    code:   Option<u16>,
+    name:   Cow<'static, str>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl TeraCatcher {
+    /// Create an instance of `TeraCatcher` for the default `TeraContextManager`. The catcher catches the errors of the status code, or all errors which have no other catchers if the code is `None`.
+    #[inline]
+    pub fn new<C: Into<Option<u16>>, S: Into<Cow<'static, str>>>(code: C, name: S) -> TeraCatcher {
+        TeraCatcher {
+            code: code.into(), name: name.into(), marker: PhantomData
+        }
+    }
+}
+
+impl<T> TeraCatcher<T> {
+    /// Render with the `TeraContextManager` of another marker type.
+    #[inline]
+    pub fn tagged<U>(self) -> TeraCatcher<U> {
+        TeraCatcher {
+            code: self.code, name: self.name, marker: PhantomData
+        }
+    }
+}
+
+impl<T> Clone for TeraCatcher<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        TeraCatcher {
+            code: self.code, name: self.name.clone(), marker: PhantomData
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<T: 'static> Handler for TeraCatcher<T> {
+    async fn handle<'r>(&self, status: Status, req: &'r Request<'_>) -> catcher::Result<'r> {
+        let cm = match req.rocket().state::<TeraContextManager<T>>() {
+            Some(cm) => cm,
+            None => {
+                rocket::error!(
+                    "TeraContextManager is not managed. Attach the fairing of TeraResponse."
+                );
+
+                return (status, status.to_string()).respond_to(req);
+            },
+        };
+
+        let context = json!({
+            "status": status.code,
+            "reason": status.reason_lossy(),
+            "path": req.uri().path().as_str(),
+        });
+
+        let response =
+            match cm.try_build(&EtagIfNoneMatch::default(), true, self.name.as_ref(), context) {
+                Ok(response) => response,
+                Err(err) => {
+                    rocket::error!("Failed to render the error page `{}`: {}", self.name, err);
+
+                    return (status, status.to_string()).respond_to(req);
+                },
+            };
+
+        // error pages must neither be answered with 304 Not Modified nor be stored by caches, and they are rendered without flash messages, which are left to the pages which show them
+        let mut response = response
+            .last_modified(None)
+            .cache_control(Some(CacheControl::NoStore))
+            .respond_to(req)?;
+
+        response.set_status(status);
+
+        Ok(response)
+    }
+}
+
+impl<T: 'static> From<TeraCatcher<T>> for Catcher {
+    #[inline]
+    fn from(catcher: TeraCatcher<T>) -> Self {
+        let mut result = Catcher::new(catcher.code, catcher.clone());
+        result.name = Some(format!("TeraCatcher({})", catcher.name).into());
+
+        result
+    }
+}
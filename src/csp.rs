@@ -0,0 +1,59 @@
+use std::sync::{Arc, OnceLock};
+
+use rocket::{request::Request, response::Builder};
+
+use crate::{functions::generate_random_token, placeholder::ResponseValue};
+
+/// The name of the variable of templates which holds the nonce of the `Content-Security-Policy` header.
+const CSP_NONCE_VARIABLE: &str = "csp_nonce";
+
+/// The text in a policy which is replaced by the nonce.
+const POLICY_NONCE_PLACEHOLDER: &str = "{nonce}";
+
+/// The nonce of the `Content-Security-Policy` header, which is provided to templates as `csp_nonce`.
+#[derive(Debug)]
+pub(crate) struct CspNonce {
+    policy: Arc<str>,
+}
+
+impl CspNonce {
+    #[inline]
+    pub(crate) fn new<S: Into<String>>(policy: S) -> CspNonce {
+        CspNonce {
+            policy: Arc::from(policy.into())
+        }
+    }
+}
+
+impl ResponseValue for CspNonce {
+    #[inline]
+    fn name(&self) -> &'static str {
+        CSP_NONCE_VARIABLE
+    }
+
+    #[inline]
+    fn placeholder(&self) -> &'static str {
+        static PLACEHOLDER: OnceLock<String> = OnceLock::new();
+
+        PLACEHOLDER.get_or_init(generate_random_token)
+    }
+
+    /// Send the `Content-Security-Policy` header with a new nonce, and get the nonce.
+    #[inline]
+    fn generate(&self, _request: &Request<'_>, response: &mut Builder<'_>) -> String {
+        let nonce = generate_random_token();
+
+        response.raw_header(
+            "Content-Security-Policy",
+            self.policy.replace(POLICY_NONCE_PLACEHOLDER, &nonce),
+        );
+
+        nonce
+    }
+
+    /// A new nonce, which is not in any header, so the HTML is not protected by the policy unless it is sent with a policy of the nonce.
+    #[inline]
+    fn detached(&self) -> String {
+        generate_random_token()
+    }
+}
@@ -0,0 +1,244 @@
+use std::{collections::HashMap, convert::Infallible, ops::Deref, sync::OnceLock};
+
+use rocket::{
+    data::{self, Data, FromData},
+    form::{self, DataField, Errors, Form, FromForm, Options, ValueField},
+    http::{Cookie, Status},
+    outcome::Outcome,
+    request::{self, FromRequest, Request},
+    response::Builder,
+};
+use tera::{Function, Value};
+
+use crate::{functions::generate_random_token, placeholder::ResponseValue};
+
+/// The name of the cookie, the variable of templates and the field of forms which hold the CSRF token.
+pub(crate) const CSRF_TOKEN_NAME: &str = "csrf_token";
+
+/// The name of the function of templates which renders a hidden field of the CSRF token.
+pub(crate) const CSRF_FIELD_FUNCTION: &str = "csrf_field";
+
+/// The text rendered for `csrf_token`, which is replaced by the CSRF token of the request when a response is sent.
+#[inline]
+fn token_placeholder() -> &'static str {
+    static PLACEHOLDER: OnceLock<String> = OnceLock::new();
+
+    PLACEHOLDER.get_or_init(generate_random_token)
+}
+
+/// The CSRF token of the request of a response, which is provided to templates as `csrf_token`.
+#[derive(Debug)]
+pub(crate) struct CsrfTokenValue;
+
+impl ResponseValue for CsrfTokenValue {
+    #[inline]
+    fn name(&self) -> &'static str {
+        CSRF_TOKEN_NAME
+    }
+
+    #[inline]
+    fn placeholder(&self) -> &'static str {
+        token_placeholder()
+    }
+
+    #[inline]
+    fn generate(&self, request: &Request<'_>, _response: &mut Builder<'_>) -> String {
+        request_token(request).to_string()
+    }
+
+    /// Without a request, there is no token, so forms in the HTML are rejected by `CsrfForm`.
+    #[inline]
+    fn detached(&self) -> String {
+        String::new()
+    }
+
+    #[inline]
+    fn client_value(&self, request: &Request<'_>) -> Option<String> {
+        Some(request_token(request).to_string())
+    }
+}
+
+/// The CSRF token of a request, which is kept in the local cache of the request.
+struct RequestToken(String);
+
+/// Get the CSRF token from the private cookie of a request. If there is no valid one, a new token is generated and set as the private cookie.
+fn request_token<'r>(req: &'r Request<'_>) -> &'r str {
+    &req.local_cache(|| {
+        let cookies = req.cookies();
+
+        match cookies.get_private(CSRF_TOKEN_NAME) {
+            // the token replaces its placeholder in place, so it must be as long as the placeholder
+            Some(cookie) if cookie.value().len() == token_placeholder().len() => {
+                RequestToken(cookie.value().to_string())
+            },
+            _ => {
+                let token = generate_random_token();
+
+                cookies.add_private(Cookie::new(CSRF_TOKEN_NAME, token.clone()));
+
+                RequestToken(token)
+            },
+        }
+    })
+    .0
+}
+
+/// Compare two tokens in constant time.
+#[inline]
+fn token_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// The `csrf_field` function of templates.
+pub(crate) struct CsrfField;
+
+impl Function for CsrfField {
+    #[inline]
+    fn call(&self, _args: &HashMap<String, Value>) -> tera::Result<Value> {
+        Ok(Value::String(format!(
+            "<input type=\"hidden\" name=\"{}\" value=\"{}\">",
+            CSRF_TOKEN_NAME,
+            token_placeholder()
+        )))
+    }
+
+    #[inline]
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// A request guard which gets the CSRF token of a request, e.g. to send it with requests made by scripts. If the request has no valid token, a new one is generated and set as the private cookie.
+#[derive(Debug, Clone, Copy)]
+pub struct CsrfToken<'r> {
+    token: &'r str,
+}
+
+impl<'r> CsrfToken<'r> {
+    /// Get the token.
+    #[inline]
+    pub fn as_str(&self) -> &'r str {
+        self.token
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CsrfToken<'r> {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        Outcome::Success(CsrfToken {
+            token: request_token(req)
+        })
+    }
+}
+
+rocket::tokio::task_local! {
+    /// The CSRF token of the request whose form is being parsed by `CsrfForm`.
+    static EXPECTED_TOKEN: String;
+}
+
+/// A form with the `csrf_token` field split off. Its fields are only parsed after the token is verified, so a forged form never buffers any data.
+struct CsrfFields<T> {
+    form: Option<T>,
+}
+
+/// Whether the `csrf_token` field of a form being parsed is verified.
+enum TokenState {
+    Pending,
+    Valid,
+    Invalid,
+}
+
+#[rocket::async_trait]
+impl<'r, T: FromForm<'r>> FromForm<'r> for CsrfFields<T> {
+    type Context = (TokenState, T::Context);
+
+    #[inline]
+    fn init(opts: Options) -> Self::Context {
+        (TokenState::Pending, T::init(opts))
+    }
+
+    fn push_value(ctxt: &mut Self::Context, field: ValueField<'r>) {
+        if field.name == CSRF_TOKEN_NAME {
+            let valid = matches!(ctxt.0, TokenState::Pending)
+                && EXPECTED_TOKEN
+                    .try_with(|expected| token_eq(field.value, expected))
+                    .unwrap_or(false);
+
+            ctxt.0 = if valid { TokenState::Valid } else { TokenState::Invalid };
+        } else if !matches!(ctxt.0, TokenState::Invalid) {
+            T::push_value(&mut ctxt.1, field);
+        }
+    }
+
+    /// Data fields, e.g. uploaded files, are skipped unless the token comes before them.
+    async fn push_data(ctxt: &mut Self::Context, field: DataField<'r, '_>) {
+        match ctxt.0 {
+            TokenState::Valid => T::push_data(&mut ctxt.1, field).await,
+            _ => ctxt.0 = TokenState::Invalid,
+        }
+    }
+
+    #[inline]
+    fn push_error(ctxt: &mut Self::Context, error: form::Error<'r>) {
+        T::push_error(&mut ctxt.1, error)
+    }
+
+    #[inline]
+    fn finalize(ctxt: Self::Context) -> form::Result<'r, Self> {
+        match ctxt.0 {
+            TokenState::Valid => Ok(CsrfFields {
+                form: Some(T::finalize(ctxt.1)?)
+            }),
+            _ => Ok(CsrfFields {
+                form: None
+            }),
+        }
+    }
+}
+
+/// A data guard which parses a form as `Form<T>` does, after checking that its `csrf_token` field matches the CSRF token in the private cookie of the request. If it does not, **403 Forbidden** is responded. The fields after an invalid token are not parsed, and data fields such as files are only accepted after a valid one, so put the field at the start of multipart forms. Render the field with `{{ csrf_field() }}` or `csrf_token` in templates.
+#[derive(Debug)]
+pub struct CsrfForm<T>(T);
+
+impl<T> CsrfForm<T> {
+    /// Get the parsed form.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for CsrfForm<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T: FromForm<'r>> FromData<'r> for CsrfForm<T> {
+    type Error = Errors<'r>;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let fields = match EXPECTED_TOKEN
+            .scope(request_token(req).to_string(), Form::<CsrfFields<T>>::from_data(req, data))
+            .await
+        {
+            Outcome::Success(form) => form.into_inner(),
+            Outcome::Error(error) => return Outcome::Error(error),
+            Outcome::Forward(forward) => return Outcome::Forward(forward),
+        };
+
+        match fields.form {
+            Some(form) => Outcome::Success(CsrfForm(form)),
+            None => Outcome::Error((
+                Status::Forbidden,
+                Errors::from(form::Error::validation("the CSRF token is invalid")),
+            )),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::UNIX_EPOCH};
+
+use rocket::{
+    data::Data,
+    fs::NamedFile,
+    http::{Method, Status},
+    request::Request,
+    response::Responder,
+    route::{Handler, Outcome, Route},
+};
+use tera::{Error as TeraError, Function, Value};
+
+/// The name of the function of templates which gets the URL of an asset.
+pub(crate) const ASSET_FUNCTION: &str = "asset";
+
+/// Static assets which can be referenced from templates through the `asset` function, which is registered by the `assets` method of the fairing of `TeraResponse`, and served by mounting this instance. In the **debug** profile, asset URLs carry a query string derived from the modification time of each file.
+#[derive(Debug, Clone)]
+pub struct TeraAssets {
+    base:  Arc<str>,
+    files: Arc<HashMap<&'static str, PathBuf>>,
+}
+
+impl TeraAssets {
+    /// Create an instance of `TeraAssets`. The `base` should be the path where this instance is going to be mounted.
+    #[inline]
+    pub fn new<S: AsRef<str>>(base: S) -> TeraAssets {
+        TeraAssets {
+            base:  Arc::from(base.as_ref().trim_end_matches('/')),
+            files: Arc::new(HashMap::new()),
+        }
+    }
+
+    /// Register an asset from a path.
+    #[inline]
+    pub fn register_asset_file<P: Into<PathBuf>>(&mut self, name: &'static str, file_path: P) {
+        Arc::make_mut(&mut self.files).insert(name.trim_start_matches('/'), file_path.into());
+    }
+
+    /// Get the URL of an asset.
+    #[inline]
+    pub fn url<S: AsRef<str>>(&self, name: S) -> Option<String> {
+        let name = name.as_ref().trim_start_matches('/');
+
+        self.files.get(name).map(|file_path| {
+            let mtime = file_path
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0);
+
+            format!("{}/{}?v={}", self.base, name, mtime)
+        })
+    }
+}
+
+impl Function for TeraAssets {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        match args.get("path") {
+            Some(Value::String(path)) => match self.url(path) {
+                Some(url) => Ok(Value::String(url)),
+                None => Err(TeraError::msg(format!("The asset `{}` is not registered.", path))),
+            },
+            _ => Err(TeraError::msg("The `asset` function needs a `path` string argument.")),
+        }
+    }
+
+    #[inline]
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+#[rocket::async_trait]
+impl Handler for TeraAssets {
+    async fn handle<'r>(&self, req: &'r Request<'_>, data: Data<'r>) -> Outcome<'r> {
+        let name = req.routed_segments(0..).collect::<Vec<&str>>().join("/");
+
+        let file_path = match self.files.get(name.as_str()) {
+            Some(file_path) => file_path,
+            None => return Outcome::forward(data, Status::NotFound),
+        };
+
+        match NamedFile::open(file_path).await {
+            Ok(file) => match file.respond_to(req) {
+                Ok(mut response) => {
+                    response.set_raw_header("Cache-Control", "no-cache");
+
+                    Outcome::Success(response)
+                },
+                Err(status) => Outcome::Error(status),
+            },
+            Err(_) => Outcome::forward(data, Status::NotFound),
+        }
+    }
+}
+
+impl From<TeraAssets> for Vec<Route> {
+    #[inline]
+    fn from(assets: TeraAssets) -> Self {
+        let mut route = Route::ranked(10, Method::Get, "/<path..>", assets);
+        route.name = Some("TeraAssets".into());
+
+        vec![route]
+    }
+}
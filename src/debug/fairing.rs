@@ -1,4 +1,10 @@
-use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    marker::PhantomData,
+    path::PathBuf,
+    sync::{PoisonError, RwLock, RwLockWriteGuard},
+};
 
 use rocket::{
     data::Data,
@@ -6,20 +12,142 @@ use rocket::{
     request::Request,
     Build, Rocket,
 };
+use serde::Serialize;
 
-use super::{ReloadableTera, TeraContextManager, TeraResponse};
+use super::{assets::ASSET_FUNCTION, ReloadableTera, TeraAssets, TeraContextManager};
+#[cfg(feature = "csrf")]
+use crate::csrf::{CsrfField, CsrfTokenValue, CSRF_FIELD_FUNCTION, CSRF_TOKEN_NAME};
+use crate::{
+    csp::CspNonce, head::mark_head_request, placeholder::Placeholders, warm::WarmEntry,
+    CacheControl, TeraResponse,
+};
 
 const FAIRING_NAME: &str = "Tera (Debug)";
 
-/// The fairing of `TeraResponse`.
-pub struct TeraResponseFairing {
+/// The fairing of `TeraResponse`. It manages a `TeraContextManager<T>`.
+pub struct TeraResponseFairing<T = ()> {
     #[allow(clippy::type_complexity)]
     pub(crate) custom_callback:
-        Box<dyn Fn(&mut MutexGuard<ReloadableTera>) -> usize + Send + Sync + 'static>,
+        Box<dyn Fn(&mut RwLockWriteGuard<ReloadableTera>) -> usize + Send + Sync + 'static>,
+    #[allow(clippy::type_complexity)]
+    pub(crate) overlays:
+        Vec<(String, Box<dyn Fn(&mut RwLockWriteGuard<ReloadableTera>) + Send + Sync + 'static>)>,
+    pub(crate) fragment_header: Cow<'static, str>,
+    pub(crate) warm_entries:    Vec<WarmEntry>,
+    pub(crate) cache_control:   Option<CacheControl>,
+    pub(crate) placeholders:    Placeholders,
+    pub(crate) assets:          Option<TeraAssets>,
+    pub(crate) marker:          PhantomData<fn() -> T>,
+}
+
+impl<T> TeraResponseFairing<T> {
+    /// Manage the `TeraContextManager` under another marker type, so that multiple template sets (e.g. for the public site and the admin panel), each with its own cache capacity and globals, can be used in the same Rocket instance. Use `&State<TeraContextManager<U>>` to get it.
+    #[inline]
+    pub fn tagged<U>(self) -> TeraResponseFairing<U> {
+        TeraResponseFairing {
+            custom_callback: self.custom_callback,
+            overlays:        self.overlays,
+            fragment_header: self.fragment_header,
+            warm_entries:    self.warm_entries,
+            cache_control:   self.cache_control,
+            placeholders:    self.placeholders,
+            assets:          self.assets,
+            marker:          PhantomData,
+        }
+    }
+
+    /// Add a named overlay, such as a theme or a tenant. The overlay starts with all templates of the base set, and templates registered by the callback override the ones with the same names. Use `TeraContextManager::build_overlay` to render with it. Templates of overlays are reloaded automatically as well.
+    #[inline]
+    pub fn overlay<S, F>(mut self, name: S, f: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&mut RwLockWriteGuard<ReloadableTera>) + Send + Sync + 'static, {
+        self.overlays.push((name.into(), Box::new(f)));
+
+        self
+    }
+
+    /// Set the request header which asks for a fragment of a page instead of the whole page. It is `HX-Request` by default. Use the `FragmentRequest` request guard to check it.
+    #[inline]
+    pub fn fragment_header<S: Into<Cow<'static, str>>>(mut self, header: S) -> Self {
+        self.fragment_header = header.into();
+
+        self
+    }
+
+    /// Set the default policy of the `Cache-Control` header of the responses built by the `TeraContextManager`. No `Cache-Control` header is sent by default. It can be overridden by the `cache_control` method of `TeraResponse`.
+    #[inline]
+    pub fn cache_control(mut self, cache_control: CacheControl) -> Self {
+        self.cache_control = Some(cache_control);
+
+        self
+    }
+
+    /// Set the policy of the `Content-Security-Policy` header of the HTML responses built by the `TeraContextManager`, e.g. `script-src 'nonce-{nonce}'`. Every `{nonce}` in the policy is replaced by a new nonce for each response, which is provided to templates as `csp_nonce`. Templates are rendered with a placeholder of the nonce, which is replaced when a response is sent, so responses of the cache of `tera_response_cache!` get new nonces as well. The ETag does not depend on the nonce, so a response of **304 Not Modified** carries no policy and the browser keeps using the page with the nonce and the policy it stored; a nonce is new for each page that is sent, not for each view. HTML rendered by the `render` methods of the `TeraContextManager` has new nonces which are not sent in any header.
+    #[inline]
+    pub fn content_security_policy<S: Into<String>>(mut self, policy: S) -> Self {
+        self.placeholders.add(CspNonce::new(policy));
+
+        self
+    }
+
+    /// Provide the CSRF token of each request to templates as `csrf_token`, and the `csrf_field()` function which renders a hidden field of it. The token is kept in a private cookie, which is set when a request has no valid one, so a `secret_key` needs to be configured in the **release** profile. Templates are rendered with a placeholder of the token, which is replaced when a response is sent, so responses of the cache of `tera_response_cache!` carry the token of their requests. The token is mixed into the ETag of HTML responses, which are sent with `Cache-Control: private, no-cache` and without `Last-Modified`. Use the `CsrfForm` data guard to validate submitted forms.
+    #[cfg(feature = "csrf")]
+    #[inline]
+    pub fn csrf(mut self) -> Self {
+        self.placeholders.add(CsrfTokenValue);
+
+        self
+    }
+
+    /// Register the `asset` function of templates, which gets the URL of an asset of the `TeraAssets`, e.g. `{{ asset(path="css/app.css") }}`. Mount the same `TeraAssets` to serve the assets.
+    #[inline]
+    pub fn assets(mut self, assets: TeraAssets) -> Self {
+        self.assets = Some(assets);
+
+        self
+    }
+
+    /// Render a template into the cache under a key when the Rocket instance is ignited, so that the first request of the key hits the cache of `tera_response_cache!`. The HTML is minified. The context is provided by the closure. If the template cannot be rendered, the launch is aborted.
+    #[inline]
+    pub fn warm<K, S, V, F>(mut self, key: K, name: S, f: F) -> Self
+    where
+        K: Into<String>,
+        S: Into<String>,
+        V: Serialize,
+        F: Fn() -> V + Send + Sync + 'static, {
+        self.warm_entries.push(WarmEntry::new(key.into(), name.into(), f));
+
+        self
+    }
+
+    /// Set the number of shards of the cache. The cache is generated only when you are using the **release** profile, so it has no effect here.
+    #[inline]
+    pub fn cache_shards(self, _shards: usize) -> Self {
+        self
+    }
+
+    /// Limit the cache by a byte budget instead of its capacity in entries. The cache is generated only when you are using the **release** profile, so it has no effect here.
+    #[inline]
+    pub fn cache_bytes(self, _max_bytes: usize) -> Self {
+        self
+    }
+
+    /// Persist the cache into a directory as a second tier. The cache is generated only when you are using the **release** profile, so it has no effect here.
+    #[inline]
+    pub fn disk_cache<P: Into<PathBuf>>(self, _directory: P) -> Self {
+        self
+    }
+
+    /// Set a version which is part of the version of the templates. The version is computed only when you are using the **release** profile, so it has no effect here.
+    #[inline]
+    pub fn cache_version<S: Into<String>>(self, _version: S) -> Self {
+        self
+    }
 }
 
 #[rocket::async_trait]
-impl Fairing for TeraResponseFairing {
+impl<T: 'static> Fairing for TeraResponseFairing<T> {
     #[inline]
     fn info(&self) -> Info {
         Info {
@@ -29,33 +157,91 @@ impl Fairing for TeraResponseFairing {
 
     #[inline]
     async fn on_ignite(&self, rocket: Rocket<Build>) -> Result<Rocket<Build>, Rocket<Build>> {
-        let tera = Mutex::new(ReloadableTera::new());
+        let tera = RwLock::new(ReloadableTera::new());
+
+        let cache_capacity = {
+            let mut tera = tera.write().unwrap_or_else(PoisonError::into_inner);
 
-        let cache_capacity =
-            (self.custom_callback)(&mut tera.lock().unwrap_or_else(PoisonError::into_inner));
+            let cache_capacity = (self.custom_callback)(&mut tera);
 
-        let state = TeraContextManager::new(tera, cache_capacity);
+            if let Some(assets) = self.assets.as_ref() {
+                tera.register_function(ASSET_FUNCTION, assets.clone());
+            }
+
+            #[cfg(feature = "csrf")]
+            if self.placeholders.contains(CSRF_TOKEN_NAME) {
+                tera.register_function(CSRF_FIELD_FUNCTION, CsrfField);
+            }
+
+            tera.refresh_block_templates();
+
+            cache_capacity
+        };
+
+        let mut overlays = HashMap::with_capacity(self.overlays.len());
+
+        {
+            let base = tera.read().unwrap_or_else(PoisonError::into_inner);
+
+            for (name, f) in self.overlays.iter() {
+                let overlay = RwLock::new(ReloadableTera::new_overlay(&base));
+
+                {
+                    let mut overlay = overlay.write().unwrap_or_else(PoisonError::into_inner);
+
+                    f(&mut overlay);
+
+                    overlay.track_overlay(&base);
+                }
+
+                overlays.insert(name.clone(), overlay);
+            }
+        }
+
+        let state = TeraContextManager::<T>::new(
+            tera,
+            overlays,
+            self.fragment_header.clone(),
+            cache_capacity,
+            self.cache_control.clone(),
+            self.placeholders.clone(),
+        );
+
+        // the cache is generated only in the release profile, so entries are rendered only to check them
+        {
+            let tera = state.tera.read().unwrap_or_else(PoisonError::into_inner);
+
+            for entry in self.warm_entries.iter() {
+                if let Err(message) = entry.render(&tera, &self.placeholders) {
+                    rocket::error!("{}", message);
+
+                    return Err(rocket);
+                }
+            }
+        }
 
         Ok(rocket.manage(state))
     }
 
     #[inline]
     async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        mark_head_request(req);
+
         let cm = req
             .rocket()
-            .state::<TeraContextManager>()
+            .state::<TeraContextManager<T>>()
             .expect("TeraContextManager registered in on_attach");
 
-        cm.tera.lock().unwrap_or_else(PoisonError::into_inner).reload_if_needed().unwrap();
+        cm.reload_if_needed().unwrap();
     }
 }
 
 impl TeraResponse {
     /// Create the fairing of `TeraResponse`.
     #[inline]
-    pub fn fairing<F>(f: F) -> impl Fairing
+    pub fn fairing<F>(f: F) -> TeraResponseFairing
     where
-        F: Fn(&mut MutexGuard<ReloadableTera>) + Send + Sync + 'static, {
+        F: Fn(&mut RwLockWriteGuard<ReloadableTera>) + Send + Sync + 'static, {
         let f = Box::new(f);
 
         TeraResponseFairing {
@@ -64,16 +250,30 @@ impl TeraResponse {
 
                 crate::DEFAULT_CACHE_CAPACITY
             }),
+            overlays:        Vec::new(),
+            fragment_header: Cow::Borrowed(crate::fragment::DEFAULT_FRAGMENT_HEADER),
+            warm_entries:    Vec::new(),
+            cache_control:   None,
+            placeholders:    Placeholders::new(),
+            assets:          None,
+            marker:          PhantomData,
         }
     }
 
     /// Create the fairing of `TeraResponse` and set the cache capacity.
     #[inline]
-    pub fn fairing_cache<F>(f: F) -> impl Fairing
+    pub fn fairing_cache<F>(f: F) -> TeraResponseFairing
     where
-        F: Fn(&mut MutexGuard<ReloadableTera>) -> usize + Send + Sync + 'static, {
+        F: Fn(&mut RwLockWriteGuard<ReloadableTera>) -> usize + Send + Sync + 'static, {
         TeraResponseFairing {
-            custom_callback: Box::new(f)
+            custom_callback: Box::new(f),
+            overlays:        Vec::new(),
+            fragment_header: Cow::Borrowed(crate::fragment::DEFAULT_FRAGMENT_HEADER),
+            warm_entries:    Vec::new(),
+            cache_control:   None,
+            placeholders:    Placeholders::new(),
+            assets:          None,
+            marker:          PhantomData,
         }
     }
 }
@@ -21,21 +21,82 @@ macro_rules! tera_resources_initialize {
     };
 }
 
-/// Used for wrapping a `TeraResponse` and its constructor, and use a **key** to cache its HTML and ETag in memory. The cache is generated only when you are using the **release** profile.
+/// Used in the fairing of `TeraResponse` to include message catalogs of locales into your executable binary file, and register them as the `t` function of Tera. You need to specify each catalog's locale and its path relative to the directory containing the manifest of your package. In order to reduce the compilation time and allow to hot-reload catalogs, files are compiled into your executable binary file together, only when you are using the **release** profile.
+#[macro_export]
+macro_rules! tera_catalogs_initialize {
+    ( $tera:expr, $($locale:expr => $path:expr), * $(,)* ) => {
+        {
+            use ::std::collections::HashSet;
+
+            let mut set: HashSet<&'static str> = HashSet::new();
+
+            $(
+                if set.contains($locale) {
+                    panic!("The locale `{}` is duplicated.", $locale);
+                } else {
+                    $tera.register_catalog_file($locale, $crate::manifest_dir_macros::not_directory_path!($path)).unwrap();
+
+                    set.insert($locale);
+                }
+            )*
+        }
+    };
+}
+
+/// Used for registering static assets into a `TeraAssets` instance. You need to specify each asset's name and its path relative to the directory containing the manifest of your package. Assets are compiled into your executable binary file together, only when you are using the **release** profile.
+#[macro_export]
+macro_rules! tera_assets_initialize {
+    ( $assets:expr, $($name:expr => $path:expr), * $(,)* ) => {
+        {
+            use ::std::collections::HashSet;
+
+            let mut set: HashSet<&'static str> = HashSet::new();
+
+            $(
+                if set.contains($name) {
+                    panic!("The name `{}` is duplicated.", $name);
+                } else {
+                    $assets.register_asset_file($name, $crate::manifest_dir_macros::not_directory_path!($path));
+
+                    set.insert($name);
+                }
+            )*
+        }
+    };
+}
+
+/// Used for wrapping a `TeraResponse` and its constructor, and use a **key** to cache its HTML and ETag in memory. The cache is generated only when you are using the **release** profile. Prefix the arguments with `auto` and give the name and the context of a template instead of a key and a constructor to derive the key from the name, the context and the minify flag, e.g. `tera_response_cache!(auto cm, etag_if_none_match, "index", context)`. Prefix the arguments with `cache_control policy =>` to override the default policy of the `Cache-Control` header for the entry, e.g. `tera_response_cache!(cache_control CacheControl::NoCache => cm, etag_if_none_match, "index", { ... })`. When the request carries a flash message, a response built by `tera_response!` is rendered again with it instead of being served from the cache.
 #[macro_export]
 macro_rules! tera_response_cache {
+    ( auto $cm:expr, $etag_if_none_match:expr, $name:expr, $data:expr ) => {
+        $crate::tera_response_cache!(auto enable_minify $cm, $etag_if_none_match, $name, $data)
+    };
+    ( auto enable_minify $cm:expr, $etag_if_none_match:expr, $name:expr, $data:expr ) => {
+        $crate::tera_response_cache!($cm, $etag_if_none_match, "", {
+            $crate::tera_response!(enable_minify $cm, $crate::EtagIfNoneMatch::default(), $name, $data)
+        })
+    };
+    ( auto disable_minify $cm:expr, $etag_if_none_match:expr, $name:expr, $data:expr ) => {
+        $crate::tera_response_cache!($cm, $etag_if_none_match, "", {
+            $crate::tera_response!(disable_minify $cm, $crate::EtagIfNoneMatch::default(), $name, $data)
+        })
+    };
+    ( cache_control $cache_control:expr => $cm:expr, $etag_if_none_match:expr, $key:expr, $gen:block ) => {
+        $crate::tera_response_cache!($cm, $etag_if_none_match, $key, $gen)
+            .cache_control(::std::option::Option::Some($cache_control))
+    };
     ($cm:expr, $etag_if_none_match:expr, $key:expr, $gen:block) => {{
         #[allow(unused_variables)]
         let __a = &$cm;
         #[allow(unused_variables)]
         let __a = &$key;
 
-        let res = $gen;
+        let res = $gen.into_cached();
 
         if res.weak_eq(&$etag_if_none_match) {
-            $crate::TeraResponse::not_modified()
+            res.into_not_modified()
         } else {
-            res
+            res.revalidate(&$etag_if_none_match)
         }
     }};
 }
@@ -1,27 +1,219 @@
-use std::sync::{Mutex, PoisonError};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, PoisonError, RwLock,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use serde::Serialize;
-use tera::Context;
+use tera::{Context, Error as TeraError, Tera};
 
-use super::{ReloadableTera, TeraResponse};
-use crate::{functions::compute_data_etag, EtagIfNoneMatch};
+use super::ReloadableTera;
+use crate::{
+    flash::PageSource,
+    fragment::FragmentRequest,
+    functions::{compute_context_etag, compute_data_etag, spawn_blocking},
+    i18n::{expand_locales, resolve_localized_name, LocaleScope},
+    negotiation::build_from_json,
+    placeholder::Placeholders,
+    stream::{render_stream, StreamSource},
+    CacheControl, CacheStats, EtagIfNoneMatch, HeadRequest, ResponseFormat, TeraResponse,
+    TeraTemplate,
+};
 
-/// To monitor the state of Tera.
+/// To monitor the state of Tera. The type parameter is a marker which distinguishes template sets managed by the same Rocket instance.
 #[derive(Educe)]
 #[educe(Debug)]
-pub struct TeraContextManager {
-    pub tera: Mutex<ReloadableTera>,
+pub struct TeraContextManager<T = ()> {
+    pub tera:                   Arc<RwLock<ReloadableTera>>,
+    overlays:                   HashMap<String, Arc<RwLock<ReloadableTera>>>,
+    pub(crate) fragment_header: Cow<'static, str>,
+    ignited_at:                 u128,
+    reloads:                    AtomicU64,
+    cache_control:              Option<CacheControl>,
+    placeholders:               Placeholders,
+    #[educe(Debug(ignore))]
+    marker:                     PhantomData<fn() -> T>,
 }
 
-impl TeraContextManager {
+impl<T> TeraContextManager<T> {
     #[inline]
-    pub(crate) fn new(tera: Mutex<ReloadableTera>, _cache_capacity: usize) -> TeraContextManager {
+    pub(crate) fn new(
+        tera: RwLock<ReloadableTera>,
+        overlays: HashMap<String, RwLock<ReloadableTera>>,
+        fragment_header: Cow<'static, str>,
+        _cache_capacity: usize,
+        cache_control: Option<CacheControl>,
+        placeholders: Placeholders,
+    ) -> TeraContextManager<T> {
         TeraContextManager {
-            tera,
+            tera: Arc::new(tera),
+            overlays: overlays
+                .into_iter()
+                .map(|(overlay_name, overlay)| (overlay_name, Arc::new(overlay)))
+                .collect(),
+            fragment_header,
+            ignited_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_nanos())
+                .unwrap_or_default(),
+            reloads: AtomicU64::new(0),
+            cache_control,
+            placeholders,
+            marker: PhantomData,
         }
     }
 
-    /// Build a `TeraResponse`.
+    /// Reload the base template set and the overlays if needed. Only take the exclusive locks when some files have been modified, so that renders are not blocked.
+    pub(crate) fn reload_if_needed(&self) -> Result<(), TeraError> {
+        let base_needs_reload =
+            self.tera.read().unwrap_or_else(PoisonError::into_inner).needs_reload()?;
+
+        if base_needs_reload {
+            self.tera.write().unwrap_or_else(PoisonError::into_inner).reload_if_needed()?;
+
+            let base = self.tera.read().unwrap_or_else(PoisonError::into_inner);
+
+            for overlay in self.overlays.values() {
+                overlay.write().unwrap_or_else(PoisonError::into_inner).rebase(&base)?;
+            }
+
+            self.reloads.fetch_add(1, Ordering::Relaxed);
+        } else {
+            for overlay in self.overlays.values() {
+                let needs_reload =
+                    overlay.read().unwrap_or_else(PoisonError::into_inner).needs_reload()?;
+
+                if needs_reload {
+                    overlay.write().unwrap_or_else(PoisonError::into_inner).reload_if_needed()?;
+
+                    self.reloads.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The version of the templates, which changes whenever they are reloaded.
+    #[inline]
+    fn template_version(&self) -> String {
+        format!("{}-{}", self.ignited_at, self.reloads.load(Ordering::Relaxed))
+    }
+
+    /// Convert a context, and provide the placeholders of the values which differ for each response, such as `csp_nonce`.
+    #[inline]
+    fn context<V: Serialize>(&self, context: V) -> Context {
+        let mut context = Context::from_serialize(context).unwrap();
+
+        self.placeholders.insert_into(&mut context);
+
+        context
+    }
+
+    /// Get the template set of an overlay. An unknown overlay is an error like an unknown template, so it panics.
+    #[inline]
+    fn overlay_tera(&self, overlay: &str) -> &Arc<RwLock<ReloadableTera>> {
+        self.overlays.get(overlay).unwrap_or_else(|| {
+            panic!("The overlay `{}` is not added to the fairing of TeraResponse.", overlay)
+        })
+    }
+
+    /// Get the source of a page rendered with the base template set.
+    #[inline]
+    fn page_source(&self, name: &str, context: Context, minify: bool) -> PageSource {
+        let tera = self.tera.clone();
+        let name: Arc<str> = Arc::from(name);
+
+        PageSource::new(
+            name.clone(),
+            context,
+            minify,
+            Arc::new(move |context| {
+                tera.read().unwrap_or_else(PoisonError::into_inner).render(&name, context)
+            }),
+        )
+    }
+
+    /// Get the source of a page rendered with a template localized for the first possible locale of the given locales sorted by preference.
+    #[inline]
+    fn localized_source<L: AsRef<str>>(
+        &self,
+        locales: &[L],
+        name: &str,
+        context: Context,
+        minify: bool,
+    ) -> PageSource {
+        let locales = expand_locales(locales);
+
+        let name: Arc<str> = {
+            let tera = self.tera.read().unwrap_or_else(PoisonError::into_inner);
+
+            Arc::from(resolve_localized_name(&locales, name, |name| {
+                tera.get_template(name).is_ok()
+            }))
+        };
+
+        let tera = self.tera.clone();
+
+        PageSource::new(
+            name.clone(),
+            context,
+            minify,
+            Arc::new(move |context| {
+                let _scope = LocaleScope::enter(locales.clone());
+
+                tera.read().unwrap_or_else(PoisonError::into_inner).render(&name, context)
+            }),
+        )
+    }
+
+    /// Get the source of a page which is only a block of a template.
+    #[inline]
+    fn block_source(&self, name: &str, block: &str, context: Context, minify: bool) -> PageSource {
+        let tera = self.tera.clone();
+        let name: Arc<str> = Arc::from(name);
+        let block = block.to_string();
+
+        PageSource::new(
+            name.clone(),
+            context,
+            minify,
+            Arc::new(move |context| {
+                tera.read()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .render_block(&name, &block, context)
+            }),
+        )
+    }
+
+    /// Get the source of a page rendered with the template set of an overlay.
+    #[inline]
+    fn overlay_source(
+        &self,
+        overlay: &str,
+        name: &str,
+        context: Context,
+        minify: bool,
+    ) -> PageSource {
+        let tera = self.overlay_tera(overlay).clone();
+        let name: Arc<str> = Arc::from(name);
+
+        PageSource::new(
+            name.clone(),
+            context,
+            minify,
+            Arc::new(move |context| {
+                tera.read().unwrap_or_else(PoisonError::into_inner).render(&name, context)
+            }),
+        )
+    }
+
+    /// Build a `TeraResponse`. The template is rendered when the response is sent, with the flash message of the request as `flash` if there is one. If rendering fails, the error is logged and **500 Internal Server Error** is responded.
     #[inline]
     pub fn build<S: AsRef<str>, V: Serialize>(
         &self,
@@ -30,31 +222,477 @@ impl TeraContextManager {
         name: S,
         context: V,
     ) -> TeraResponse {
+        let name = name.as_ref();
+
+        let last_modified = self.last_modified(name);
+
+        TeraResponse::build_page(
+            etag_if_none_match,
+            None,
+            self.page_source(name, self.context(context), minify),
+            false,
+        )
+        .template_modified(last_modified)
+        .cache_control(self.cache_control.clone())
+        .placeholders(self.placeholders.clone())
+    }
+
+    /// Build a `TeraResponse` with the template of a `TeraTemplate` type as `build` does. The template is registered the first time, unless it is already registered in the fairing, and reloaded like other templates after that.
+    pub(crate) fn build_template<V: TeraTemplate>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        context: &V,
+    ) -> Result<TeraResponse, TeraError> {
+        let registered =
+            self.tera.read().unwrap_or_else(PoisonError::into_inner).get_template(V::NAME).is_ok();
+
+        if !registered {
+            let mut tera = self.tera.write().unwrap_or_else(PoisonError::into_inner);
+
+            // another request may have registered it while waiting for the lock
+            if tera.get_template(V::NAME).is_err() {
+                V::register(&mut tera);
+
+                tera.get_template(V::NAME)?;
+
+                tera.refresh_block_templates();
+            }
+        }
+
+        Ok(self.build(etag_if_none_match, V::MINIFY, V::NAME, context))
+    }
+
+    /// Build a `TeraResponse` by rendering the template right away without a flash message, or get the error of rendering it, e.g. for error pages.
+    #[inline]
+    pub(crate) fn try_build<S: AsRef<str>, V: Serialize>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        context: V,
+    ) -> Result<TeraResponse, TeraError> {
+        let name = name.as_ref();
+        let context = self.context(context);
+
+        let last_modified = self.last_modified(name);
+
+        let html =
+            self.tera.read().unwrap_or_else(PoisonError::into_inner).render(name, &context)?;
+
+        Ok(build_from_html(etag_if_none_match, minify, html)
+            .template_modified(last_modified)
+            .cache_control(self.cache_control.clone())
+            .placeholders(self.placeholders.clone()))
+    }
+
+    /// Build a `TeraResponse` with the template localized for the first possible locale of the given locales sorted by preference. For example, `index.de` is used instead of `index` if it is registered. Locale tags are normalized, so localized templates are named with canonical tags like `index.de-CH`. The `t` function of templates also uses these locales. The response varies on `Accept-Language`.
+    #[inline]
+    pub fn build_localized<L: AsRef<str>, S: AsRef<str>, V: Serialize>(
+        &self,
+        locales: &[L],
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        context: V,
+    ) -> TeraResponse {
+        let last_modified = self.localized_last_modified(locales, name.as_ref());
+
+        TeraResponse::build_page(
+            etag_if_none_match,
+            None,
+            self.localized_source(locales, name.as_ref(), self.context(context), minify),
+            false,
+        )
+        .template_modified(last_modified)
+        .cache_control(self.cache_control.clone())
+        .placeholders(self.placeholders.clone())
+        .vary("Accept-Language")
+    }
+
+    /// Build a `TeraResponse` whose ETag is computed from the version of the templates, the name of the template, the minify flag and the context, instead of the rendered HTML. If the ETag matches, the template is neither rendered nor minified. The version changes whenever the templates are reloaded.
+    #[inline]
+    pub fn build_with_context_etag<S: AsRef<str>, V: Serialize>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        context: V,
+    ) -> TeraResponse {
+        let name = name.as_ref();
+
+        let etag = compute_context_etag(&self.template_version(), name, minify, &context);
+
+        let last_modified = self.last_modified(name);
+
+        TeraResponse::build_page(
+            etag_if_none_match,
+            Some(etag),
+            self.page_source(name, self.context(context), minify),
+            false,
+        )
+        .template_modified(last_modified)
+        .cache_control(self.cache_control.clone())
+        .placeholders(self.placeholders.clone())
+    }
+
+    /// Build a `TeraResponse` with only a block of a template, e.g. for htmx or Turbo to update a part of a page. The block is cut out of the whole page, so `super()` still works and the context must be the one of the page. Its ETag is computed from the rendered block, so it never matches the ETag of the whole page.
+    #[inline]
+    pub fn build_block<S: AsRef<str>, B: AsRef<str>, V: Serialize>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        block: B,
+        context: V,
+    ) -> TeraResponse {
+        let last_modified = self.last_modified(name.as_ref());
+
+        TeraResponse::build_page(
+            etag_if_none_match,
+            None,
+            self.block_source(name.as_ref(), block.as_ref(), self.context(context), minify),
+            false,
+        )
+        .template_modified(last_modified)
+        .cache_control(self.cache_control.clone())
+        .placeholders(self.placeholders.clone())
+    }
+
+    /// Build a `TeraResponse` with only a block of a template if a fragment is requested, or with the whole template otherwise. The request header of fragments is sent in the `Vary` header.
+    #[inline]
+    pub fn build_fragment<S: AsRef<str>, B: AsRef<str>, V: Serialize>(
+        &self,
+        fragment_request: &FragmentRequest<T>,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        block: B,
+        context: V,
+    ) -> TeraResponse {
+        let response = if fragment_request.is_requested() {
+            self.build_block(etag_if_none_match, minify, name, block, context)
+        } else {
+            self.build(etag_if_none_match, minify, name, context)
+        };
+
+        // caches must not serve a fragment for a request of the whole page, or the reverse
+        response.vary(self.fragment_header.clone())
+    }
+
+    /// Build a `TeraResponse` with only the headers for a `HEAD` request, or the same as `build` for other requests. The template is still rendered to compute its ETag, but it is not minified, and `Content-Length` is not sent because the length of the minified HTML is unknown. A response of the cache of `tera_response_cache!` does not need this, because its headers are computed from the cache.
+    #[inline]
+    pub fn build_head<S: AsRef<str>, V: Serialize>(
+        &self,
+        head_request: &HeadRequest,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        context: V,
+    ) -> TeraResponse {
+        if head_request.is_head() {
+            let last_modified = self.last_modified(name.as_ref());
+
+            build_head_from_html(etag_if_none_match, self.render_with_placeholders(name, context))
+                .template_modified(last_modified)
+                .cache_control(self.cache_control.clone())
+                .placeholders(self.placeholders.clone())
+        } else {
+            self.build(etag_if_none_match, minify, name, context)
+        }
+    }
+
+    /// Build a `TeraResponse` in the format preferred by the client. HTML is rendered from the template as `build` does, and JSON is serialized from the context, with the ETag computed from the JSON. `Accept` is sent in the `Vary` header. JSON is never stored in the cache of `tera_response_cache!`, but HTML is, so do not use the same key for a route which responds both formats.
+    #[inline]
+    pub fn build_negotiated<S: AsRef<str>, V: Serialize>(
+        &self,
+        format: ResponseFormat,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        context: V,
+    ) -> TeraResponse {
+        let response = match format {
+            ResponseFormat::Html => self.build(etag_if_none_match, minify, name, context),
+            ResponseFormat::Json => build_from_json(etag_if_none_match, context)
+                .cache_control(self.cache_control.clone())
+                .placeholders(self.placeholders.clone()),
+        };
+
+        // caches must not serve JSON to browsers, or the reverse
+        response.vary("Accept")
+    }
+
+    /// Build a `TeraResponse` with the template set of an overlay, such as a theme or a tenant. Templates of the overlay override the templates with the same names in the base set, so `extends` and `include` are resolved against the overlay first. Like a template which is not registered, an overlay which is not added to the fairing makes it panic.
+    #[inline]
+    pub fn build_overlay<O: AsRef<str>, S: AsRef<str>, V: Serialize>(
+        &self,
+        overlay: O,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        context: V,
+    ) -> TeraResponse {
+        let last_modified = self.overlay_last_modified(overlay.as_ref(), name.as_ref());
+
+        TeraResponse::build_page(
+            etag_if_none_match,
+            None,
+            self.overlay_source(overlay.as_ref(), name.as_ref(), self.context(context), minify),
+            false,
+        )
+        .template_modified(last_modified)
+        .cache_control(self.cache_control.clone())
+        .placeholders(self.placeholders.clone())
+    }
+
+    /// Build a `TeraResponse` for async handlers, whose template is rendered as `build` does, but the runtime is told to move its other tasks to other threads first, so that rendering and minifying a large page do not stall them.
+    pub async fn build_async<S: Into<String>, V: Serialize>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        context: V,
+    ) -> TeraResponse {
+        let name = name.into();
+
+        let last_modified = self.last_modified(&name);
+
+        TeraResponse::build_page(
+            etag_if_none_match,
+            None,
+            self.page_source(&name, self.context(context), minify),
+            true,
+        )
+        .template_modified(last_modified)
+        .cache_control(self.cache_control.clone())
+        .placeholders(self.placeholders.clone())
+    }
+
+    /// Build a `TeraResponse` which streams the rendered HTML with chunked transfer instead of holding it in memory as a whole, e.g. for a huge page. The template is rendered on the blocking thread pool while the body is being sent. The HTML is not minified and no ETag is sent, because both need the whole HTML. Like `build`, it panics if the template is not registered. If rendering fails in the middle, the error is logged and the response is aborted, so that the client never takes a truncated page as complete.
+    pub fn build_stream<S: Into<String>, V: Serialize>(&self, name: S, context: V) -> TeraResponse {
+        let name = name.into();
+
+        let last_modified = self.last_modified(&name);
+
+        TeraResponse::build_stream(
+            &EtagIfNoneMatch::default(),
+            None,
+            self.stream_source(name, context),
+        )
+        .template_modified(last_modified)
+        .cache_control(self.cache_control.clone())
+        .placeholders(self.placeholders.clone())
+    }
+
+    /// Build a `TeraResponse` which streams the rendered HTML as `build_stream` does, with an ETag computed from the version of the templates, the name of the template and the context as `build_with_context_etag` does. If the ETag matches, the template is not rendered.
+    pub fn build_stream_with_context_etag<S: Into<String>, V: Serialize>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        name: S,
+        context: V,
+    ) -> TeraResponse {
+        let name = name.into();
+
+        let etag = compute_context_etag(&self.template_version(), &name, false, &context);
+
+        let last_modified = self.last_modified(&name);
+
+        TeraResponse::build_stream(
+            etag_if_none_match,
+            Some(etag),
+            self.stream_source(name, context),
+        )
+        .template_modified(last_modified)
+        .cache_control(self.cache_control.clone())
+        .placeholders(self.placeholders.clone())
+    }
+
+    /// Render a template with the placeholders of the values which differ for each response.
+    #[inline]
+    fn render_with_placeholders<S: AsRef<str>, V: Serialize>(&self, name: S, context: V) -> String {
         self.tera
-            .lock()
+            .read()
             .unwrap_or_else(PoisonError::into_inner)
-            .render(name.as_ref(), &Context::from_serialize(context).unwrap())
-            .map(|html| {
-                let etag = compute_data_etag(html.as_bytes());
+            .render(name.as_ref(), &self.context(context))
+            .unwrap()
+    }
 
-                if etag_if_none_match.weak_eq(&etag) {
-                    TeraResponse::not_modified()
-                } else {
-                    let html = if minify { html_minifier::minify(html).unwrap() } else { html };
+    /// Render only a block of a template with the placeholders of the values which differ for each response.
+    #[inline]
+    fn render_block_with_placeholders<S: AsRef<str>, B: AsRef<str>, V: Serialize>(
+        &self,
+        name: S,
+        block: B,
+        context: V,
+    ) -> String {
+        self.block_source(name.as_ref(), block.as_ref(), self.context(context), false)
+            .render_html(None)
+            .unwrap()
+    }
 
-                    TeraResponse::build_not_cache(html, &etag)
-                }
-            })
+    /// Render a template with the template set of an overlay and the placeholders of the values which differ for each response.
+    #[inline]
+    fn render_overlay_with_placeholders<O: AsRef<str>, S: AsRef<str>, V: Serialize>(
+        &self,
+        overlay: O,
+        name: S,
+        context: V,
+    ) -> String {
+        self.overlay_source(overlay.as_ref(), name.as_ref(), self.context(context), false)
+            .render_html(None)
             .unwrap()
     }
 
-    /// Render a template.
+    /// Render a template. Values which differ for each response are new values which are not sent in any header, so `csp_nonce` is not protected by the `Content-Security-Policy` header of the fairing, and `csrf_token` is empty.
     #[inline]
     pub fn render<S: AsRef<str>, V: Serialize>(&self, name: S, context: V) -> String {
-        self.tera
-            .lock()
-            .unwrap_or_else(PoisonError::into_inner)
-            .render(name.as_ref(), &Context::from_serialize(context).unwrap())
+        self.placeholders.detach(self.render_with_placeholders(name, context))
+    }
+
+    /// Render only a block of a template. Values which differ for each response are handled as `render` does.
+    #[inline]
+    pub fn render_block<S: AsRef<str>, B: AsRef<str>, V: Serialize>(
+        &self,
+        name: S,
+        block: B,
+        context: V,
+    ) -> String {
+        self.placeholders.detach(self.render_block_with_placeholders(name, block, context))
+    }
+
+    /// Render a template with the template set of an overlay. Values which differ for each response are handled as `render` does.
+    #[inline]
+    pub fn render_overlay<O: AsRef<str>, S: AsRef<str>, V: Serialize>(
+        &self,
+        overlay: O,
+        name: S,
+        context: V,
+    ) -> String {
+        self.placeholders.detach(self.render_overlay_with_placeholders(overlay, name, context))
+    }
+
+    /// Render a template localized for the first possible locale of the given locales sorted by preference. Values which differ for each response are handled as `render` does.
+    #[inline]
+    pub fn render_localized<L: AsRef<str>, S: AsRef<str>, V: Serialize>(
+        &self,
+        locales: &[L],
+        name: S,
+        context: V,
+    ) -> String {
+        self.placeholders.detach(self.render_localized_with_placeholders(locales, name, context))
+    }
+
+    /// Render a template on the blocking thread pool. Values which differ for each response are handled as `render` does.
+    pub async fn render_async<S: Into<String>, V: Serialize>(&self, name: S, context: V) -> String {
+        let tera = self.tera.clone();
+        let name = name.into();
+        let context = self.context(context);
+
+        let html = spawn_blocking(move || {
+            tera.read().unwrap_or_else(PoisonError::into_inner).render(&name, &context).unwrap()
+        })
+        .await;
+
+        self.placeholders.detach(html)
+    }
+
+    /// Get the newest modification time among the file of a template and the files of its parents, e.g. to check `IfModifiedSince` before rendering. It is `None` if the template is not registered from a file. Only responses which go through `tera_response_cache!` use it as their `Last-Modified` timestamp by default, because other responses may have contexts which change more often.
+    #[inline]
+    pub fn last_modified<S: AsRef<str>>(&self, name: S) -> Option<SystemTime> {
+        self.tera.read().unwrap_or_else(PoisonError::into_inner).newest_mtime(name.as_ref(), None)
+    }
+
+    #[inline]
+    fn localized_last_modified<L: AsRef<str>>(
+        &self,
+        locales: &[L],
+        name: &str,
+    ) -> Option<SystemTime> {
+        let locales = expand_locales(locales);
+
+        let tera = self.tera.read().unwrap_or_else(PoisonError::into_inner);
+
+        let name = resolve_localized_name(&locales, name, |name| tera.get_template(name).is_ok());
+
+        tera.newest_mtime(&name, None)
+    }
+
+    #[inline]
+    fn overlay_last_modified(&self, overlay: &str, name: &str) -> Option<SystemTime> {
+        let overlay = self.overlay_tera(overlay);
+
+        let base = self.tera.read().unwrap_or_else(PoisonError::into_inner);
+
+        let overlay = overlay.read().unwrap_or_else(PoisonError::into_inner);
+
+        overlay.newest_mtime(name, Some(&base))
+    }
+
+    /// Get the source of a streamed page, which is rendered on the blocking thread pool into the body of the response. The template is checked first, so that an unknown template is not responded as **200 OK**. The templates are rendered from a snapshot, because the renderer waits for the client, which must not block reloading.
+    fn stream_source<V: Serialize>(&self, name: String, context: V) -> StreamSource {
+        let tera = {
+            let tera = self.tera.read().unwrap_or_else(PoisonError::into_inner);
+
+            tera.get_template(&name).unwrap();
+
+            Tera::clone(&tera)
+        };
+
+        StreamSource::new(self.context(context), move |context| {
+            render_stream(name.clone(), move |writer| tera.render_to(&name, &context, writer))
+        })
+    }
+
+    /// Get the statistics of the cache. The cache is generated only when you are using the **release** profile, so they are all zero here.
+    #[inline]
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats::default()
+    }
+
+    /// Get the keys of the cache. The cache is generated only when you are using the **release** profile, so there are no keys here.
+    #[inline]
+    pub fn cache_keys(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Render a localized template with the placeholders of the values which differ for each response.
+    #[inline]
+    fn render_localized_with_placeholders<L: AsRef<str>, S: AsRef<str>, V: Serialize>(
+        &self,
+        locales: &[L],
+        name: S,
+        context: V,
+    ) -> String {
+        self.localized_source(locales, name.as_ref(), self.context(context), false)
+            .render_html(None)
             .unwrap()
     }
 }
+
+#[inline]
+fn build_from_html(
+    etag_if_none_match: &EtagIfNoneMatch<'_>,
+    minify: bool,
+    html: String,
+) -> TeraResponse {
+    let etag = compute_data_etag(html.as_bytes());
+
+    if etag_if_none_match.weak_eq(&etag) {
+        TeraResponse::not_modified()
+    } else {
+        let html = if minify { html_minifier::minify(html).unwrap() } else { html };
+
+        TeraResponse::build_not_cache(html, &etag)
+    }
+}
+
+#[inline]
+fn build_head_from_html(etag_if_none_match: &EtagIfNoneMatch<'_>, html: String) -> TeraResponse {
+    let etag = compute_data_etag(html.as_bytes());
+
+    if etag_if_none_match.weak_eq(&etag) {
+        TeraResponse::not_modified()
+    } else {
+        TeraResponse::build_head(&etag)
+    }
+}
@@ -1,3 +1,5 @@
+mod assets;
+
 mod tera_response;
 
 mod reloadable;
@@ -8,7 +10,10 @@ mod fairing;
 
 mod macros;
 
+pub use assets::*;
 pub use fairing::*;
 pub use manager::*;
 pub use reloadable::*;
-pub use tera_response::*;
+
+/// The type which templates are registered into in the fairing of `TeraResponse`.
+pub type TeraRegistry = ReloadableTera;
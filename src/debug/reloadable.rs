@@ -1,17 +1,25 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    fs, iter,
     ops::{Deref, DerefMut},
-    path::PathBuf,
+    path::{Path, PathBuf},
     time::SystemTime,
 };
 
-use tera::{Error as TeraError, Tera};
+use tera::{Context, Error as TeraError, Tera};
+
+use crate::{fragment::BlockTemplates, TeraCatalogs};
 
 #[derive(Debug)]
 /// Reloadable Tera.
 pub struct ReloadableTera {
-    tera:  Tera,
-    files: HashMap<&'static str, (PathBuf, Option<SystemTime>)>,
+    tera:          Tera,
+    files:         HashMap<&'static str, (PathBuf, Option<SystemTime>)>,
+    catalogs:      TeraCatalogs,
+    catalog_files: HashMap<&'static str, (PathBuf, Option<SystemTime>)>,
+    blocks:        BlockTemplates,
+    /// The names of the templates which this overlay registers over its base. It is empty if this is not an overlay.
+    own_templates: HashSet<String>,
 }
 
 impl ReloadableTera {
@@ -23,9 +31,81 @@ impl ReloadableTera {
         ReloadableTera {
             tera,
             files: HashMap::new(),
+            catalogs: TeraCatalogs::new(),
+            catalog_files: HashMap::new(),
+            blocks: BlockTemplates::default(),
+            own_templates: HashSet::new(),
         }
     }
 
+    /// Create an instance of `ReloadableTera` as an overlay of another one. Templates registered into the overlay override the templates with the same names in the base. Call `track_overlay` after registering them.
+    #[inline]
+    pub(crate) fn new_overlay(base: &ReloadableTera) -> ReloadableTera {
+        ReloadableTera {
+            tera:          base.tera.clone(),
+            files:         HashMap::new(),
+            catalogs:      base.catalogs.clone(),
+            catalog_files: HashMap::new(),
+            blocks:        BlockTemplates::default(),
+            own_templates: HashSet::new(),
+        }
+    }
+
+    /// Remember which templates have been registered into this overlay, i.e. the ones registered from files and the ones which differ from the templates of the base, so that the others can be replaced when the base is reloaded.
+    pub(crate) fn track_overlay(&mut self, base: &ReloadableTera) {
+        let mut own_templates =
+            self.files.keys().map(|name| name.to_string()).collect::<HashSet<_>>();
+
+        for name in self.tera.get_template_names() {
+            let template = self.tera.get_template(name).unwrap();
+
+            let overridden = match base.tera.get_template(name) {
+                Ok(base_template) => base_template.ast != template.ast,
+                Err(_) => true,
+            };
+
+            if overridden {
+                own_templates.insert(name.to_string());
+            }
+        }
+
+        self.own_templates = own_templates;
+    }
+
+    /// Replace the templates which come from the base of this overlay with the reloaded ones.
+    #[inline]
+    pub(crate) fn rebase(&mut self, base: &ReloadableTera) -> Result<(), TeraError> {
+        let own_templates = &self.own_templates;
+
+        self.tera.templates.retain(|name, _| own_templates.contains(name));
+
+        // templates which already exist in the overlay are not overwritten
+        self.tera.extend(&base.tera)
+    }
+
+    /// Build the templates of blocks again after templates are changed.
+    #[inline]
+    pub(crate) fn refresh_block_templates(&mut self) {
+        self.blocks = BlockTemplates::new(&self.tera);
+    }
+
+    /// Render only a block of a template.
+    #[inline]
+    pub(crate) fn render_block(
+        &self,
+        name: &str,
+        block: &str,
+        context: &Context,
+    ) -> Result<String, TeraError> {
+        // templates loaded from files are autoescaped by their paths
+        let escape_name = match self.files.get(name) {
+            Some((file_path, _)) => file_path.to_string_lossy(),
+            None => name.into(),
+        };
+
+        self.blocks.render(name, block, &escape_name, context)
+    }
+
     /// Register a template from a path and it can be reloaded automatically.
     #[inline]
     pub fn register_template_file<P: Into<PathBuf>>(
@@ -57,27 +137,87 @@ impl ReloadableTera {
         })
     }
 
-    /// Reload templates if needed.
+    /// Register a message catalog of a locale from a path and it can be reloaded automatically. The catalogs are available through the `t` function of templates.
+    #[inline]
+    pub fn register_catalog_file<P: Into<PathBuf>>(
+        &mut self,
+        locale: &'static str,
+        file_path: P,
+    ) -> Result<(), TeraError> {
+        let file_path = file_path.into();
+
+        let metadata = file_path.metadata()?;
+
+        let mtime = metadata.modified().ok();
+
+        self.catalogs.add_raw_catalog(locale, fs::read_to_string(&file_path)?)?;
+
+        self.catalog_files.insert(locale, (file_path, mtime));
+
+        self.tera.register_function("t", self.catalogs.clone());
+
+        Ok(())
+    }
+
+    /// Get the newest modification time among the file of a template and the files of its parents. The files registered into `base` are used for the templates which are not registered from files into this one.
+    pub(crate) fn newest_mtime(
+        &self,
+        name: &str,
+        base: Option<&ReloadableTera>,
+    ) -> Option<SystemTime> {
+        let template = self.tera.get_template(name).ok()?;
+
+        iter::once(name)
+            .chain(template.parents.iter().map(String::as_str))
+            .filter_map(|name| {
+                self.files
+                    .get(name)
+                    .or_else(|| base.and_then(|base| base.files.get(name)))
+                    .and_then(|(_, mtime)| *mtime)
+            })
+            .max()
+    }
+
+    /// Check whether some templates or message catalogs need to be reloaded.
+    #[inline]
+    pub fn needs_reload(&self) -> Result<bool, TeraError> {
+        for (file_path, mtime) in self.files.values().chain(self.catalog_files.values()) {
+            if check_mtime(file_path, *mtime)?.0 {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Reload templates and message catalogs if needed.
     #[inline]
     pub fn reload_if_needed(&mut self) -> Result<(), TeraError> {
+        let mut reloaded = false;
+
         for (name, (file_path, mtime)) in &mut self.files {
-            let metadata = file_path.metadata()?;
-
-            let (reload, new_mtime) = match mtime {
-                Some(mtime) => match metadata.modified() {
-                    Ok(new_mtime) => (new_mtime > *mtime, Some(new_mtime)),
-                    Err(_) => (true, None),
-                },
-                None => match metadata.modified() {
-                    Ok(new_mtime) => (true, Some(new_mtime)),
-                    Err(_) => (true, None),
-                },
-            };
+            let (reload, new_mtime) = check_mtime(file_path, *mtime)?;
 
             if reload {
                 self.tera.add_template_file(&file_path, Some(name))?;
 
                 *mtime = new_mtime;
+
+                reloaded = true;
+            }
+        }
+
+        if reloaded {
+            self.refresh_block_templates();
+        }
+
+        for (locale, (file_path, mtime)) in &mut self.catalog_files {
+            let (reload, new_mtime) = check_mtime(file_path, *mtime)?;
+
+            if reload {
+                self.catalogs.add_raw_catalog(*locale, fs::read_to_string(&file_path)?)?;
+
+                *mtime = new_mtime;
             }
         }
 
@@ -85,6 +225,25 @@ impl ReloadableTera {
     }
 }
 
+/// Check whether a file needs to be reloaded and get its new modification time.
+fn check_mtime(
+    file_path: &Path,
+    mtime: Option<SystemTime>,
+) -> Result<(bool, Option<SystemTime>), TeraError> {
+    let metadata = file_path.metadata()?;
+
+    Ok(match mtime {
+        Some(mtime) => match metadata.modified() {
+            Ok(new_mtime) => (new_mtime > mtime, Some(new_mtime)),
+            Err(_) => (true, None),
+        },
+        None => match metadata.modified() {
+            Ok(new_mtime) => (true, Some(new_mtime)),
+            Err(_) => (true, None),
+        },
+    })
+}
+
 impl Default for ReloadableTera {
     #[inline]
     fn default() -> Self {
@@ -1,74 +1,71 @@
-use std::io::Cursor;
+use std::time::SystemTime;
 
-use rocket::{
-    http::Status,
-    request::Request,
-    response::{self, Responder, Response},
+use crate::{
+    tera_response::{TeraResponseInner, HTML_CONTENT_TYPE},
+    EntityTag, EtagIfNoneMatch, TeraResponse,
 };
 
-use crate::{EntityTag, EtagIfNoneMatch};
-
-#[derive(Debug)]
-struct TeraResponseInner {
-    content: String,
-    etag:    String,
-}
-
-#[derive(Debug)]
-/// To respond HTML.
-pub struct TeraResponse {
-    inner: Option<TeraResponseInner>,
-}
-
 impl TeraResponse {
     #[inline]
-    pub(crate) fn build_not_cache<S: Into<String>>(
-        content: S,
-        etag: &EntityTag<'static>,
+    pub(crate) fn template_modified(
+        mut self,
+        template_modified: Option<SystemTime>,
     ) -> TeraResponse {
-        TeraResponse {
-            inner: Some(TeraResponseInner {
-                content: content.into(), etag: etag.to_string()
-            }),
-        }
+        self.template_modified = template_modified;
+
+        self
     }
 
+    /// Use the modification time of the template as the `Last-Modified` timestamp of an HTML response, as a response of the cache of `tera_response_cache!` does in the **release** profile.
     #[doc(hidden)]
     #[inline]
-    pub const fn not_modified() -> TeraResponse {
-        TeraResponse {
-            inner: None
+    pub fn into_cached(mut self) -> TeraResponse {
+        if let Some(TeraResponseInner::NotCache {
+            content_type: HTML_CONTENT_TYPE, ..
+        })
+        | Some(TeraResponseInner::Page {
+            ..
+        }) = self.inner
+        {
+            self.last_modified = self.last_modified.or(self.template_modified.take());
         }
+
+        self
     }
 
+    /// Compare the ETag of a page which is rendered when the response is sent with an `If-None-Match` header, instead of the one which the page is built with.
     #[doc(hidden)]
     #[inline]
-    pub fn weak_eq(&self, etag_if_none_match: &EtagIfNoneMatch<'_>) -> bool {
-        self.inner
-            .as_ref()
-            .map(|inner| {
-                etag_if_none_match.weak_eq(unsafe {
-                    &EntityTag::with_str_unchecked(false, &inner.etag[1..(inner.etag.len() - 1)])
-                })
-            })
-            .unwrap_or(false)
+    pub fn revalidate(mut self, etag_if_none_match: &EtagIfNoneMatch<'_>) -> TeraResponse {
+        if let Some(TeraResponseInner::Page {
+            etag_if_none_match: page_etag_if_none_match, ..
+        }) = self.inner.as_mut()
+        {
+            *page_etag_if_none_match = EtagIfNoneMatch {
+                etag: etag_if_none_match.etag.clone().map(EntityTag::into_owned),
+            };
+        }
+
+        self
     }
-}
 
-impl<'r, 'o: 'r> Responder<'r, 'o> for TeraResponse {
+    #[doc(hidden)]
     #[inline]
-    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'o> {
-        let mut response = Response::build();
-
-        if let Some(inner) = self.inner {
-            response.raw_header("Content-Type", "text/html; charset=utf-8");
-            response.raw_header("Etag", inner.etag);
-
-            response.sized_body(inner.content.len(), Cursor::new(inner.content));
-        } else {
-            response.status(Status::NotModified);
-        }
+    pub fn weak_eq(&self, etag_if_none_match: &EtagIfNoneMatch<'_>) -> bool {
+        let etag = match self.inner.as_ref() {
+            Some(TeraResponseInner::NotCache {
+                etag, ..
+            })
+            | Some(TeraResponseInner::Head {
+                etag,
+            })
+            | Some(TeraResponseInner::Stream {
+                etag: Some(etag), ..
+            }) => etag,
+            _ => return false,
+        };
 
-        response.ok()
+        etag_if_none_match
+            .weak_eq(unsafe { &EntityTag::with_str_unchecked(false, &etag[1..(etag.len() - 1)]) })
     }
 }
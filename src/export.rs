@@ -0,0 +1,176 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+
+use rocket::{http::Status, local::asynchronous::Client, Build, Rocket};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{functions::compute_data_etag, TeraContextManager};
+
+/// The name of the ETag manifest written into the output directory.
+pub const EXPORT_MANIFEST_NAME: &str = "etags.json";
+
+#[derive(Debug)]
+enum ExportEntry {
+    Template { name: String, context: Value },
+    Route { uri: String },
+}
+
+/// Pre-render templates and routes of a Rocket instance into HTML files, e.g. to serve them from a CDN. Routes are requested through `rocket::local`, so no network server is launched. The ETags of the files are written into `etags.json` in the output directory. The type parameter is the marker of the `TeraContextManager` which renders the templates. Values which differ for each response, such as `csp_nonce`, are fixed in the files and no `Content-Security-Policy` header is exported, so pages which rely on nonces are not protected by a policy when they are served as files.
+///
+/// ```rust,ignore
+/// let manifest = StaticExport::new("dist")
+///     .template("about.html", "about", json!({ "title": "About" }))
+///     .route("index.html", "/")
+///     .run(rocket())
+///     .await?;
+/// ```
+#[derive(Debug)]
+pub struct StaticExport<T = ()> {
+    output_dir: PathBuf,
+    minify:     bool,
+    entries:    Vec<(String, ExportEntry)>,
+    marker:     PhantomData<fn() -> T>,
+}
+
+impl StaticExport {
+    /// Create an instance of `StaticExport` which writes files into a directory.
+    #[inline]
+    pub fn new<P: Into<PathBuf>>(output_dir: P) -> StaticExport {
+        StaticExport {
+            output_dir: output_dir.into(),
+            minify:     true,
+            entries:    Vec::new(),
+            marker:     PhantomData,
+        }
+    }
+}
+
+impl<T: 'static> StaticExport<T> {
+    /// Render templates with the `TeraContextManager` of another marker type.
+    #[inline]
+    pub fn tagged<U>(self) -> StaticExport<U> {
+        StaticExport {
+            output_dir: self.output_dir,
+            minify:     self.minify,
+            entries:    self.entries,
+            marker:     PhantomData,
+        }
+    }
+
+    /// Set whether rendered templates are minified. It is `true` by default. Responses of routes are written as they are.
+    #[inline]
+    pub fn minify(mut self, minify: bool) -> Self {
+        self.minify = minify;
+
+        self
+    }
+
+    /// Render a template with a context into a file. The path of the file is relative to the output directory.
+    #[inline]
+    pub fn template<P: Into<String>, S: Into<String>, V: Serialize>(
+        mut self,
+        file: P,
+        name: S,
+        context: V,
+    ) -> Self {
+        self.entries.push((file.into(), ExportEntry::Template {
+            name:    name.into(),
+            context: serde_json::to_value(context).unwrap(),
+        }));
+
+        self
+    }
+
+    /// Request a route with the `GET` method and write its response into a file. The path of the file is relative to the output directory.
+    #[inline]
+    pub fn route<P: Into<String>, U: Into<String>>(mut self, file: P, uri: U) -> Self {
+        self.entries.push((file.into(), ExportEntry::Route {
+            uri: uri.into()
+        }));
+
+        self
+    }
+
+    /// Ignite the Rocket instance, write all files and the ETag manifest, and return the manifest, which maps the paths of the files to their ETags.
+    pub async fn run(self, rocket: Rocket<Build>) -> io::Result<BTreeMap<String, String>> {
+        let client = Client::untracked(rocket).await.map_err(other_error)?;
+
+        let mut manifest = BTreeMap::new();
+
+        for (file, entry) in self.entries {
+            let (content, etag) = match entry {
+                ExportEntry::Template {
+                    name,
+                    context,
+                } => {
+                    let cm = client.rocket().state::<TeraContextManager<T>>().ok_or_else(|| {
+                        other_error("TeraContextManager is not managed by the Rocket instance")
+                    })?;
+
+                    let html = cm.render(&name, context);
+
+                    let etag = compute_data_etag(html.as_bytes()).to_string();
+
+                    let html = if self.minify {
+                        html_minifier::minify(html).map_err(other_error)?
+                    } else {
+                        html
+                    };
+
+                    (html.into_bytes(), etag)
+                },
+                ExportEntry::Route {
+                    uri,
+                } => {
+                    let response = client.get(uri.as_str()).dispatch().await;
+
+                    if response.status() != Status::Ok {
+                        return Err(other_error(format!(
+                            "`{}` responds with {}",
+                            uri,
+                            response.status()
+                        )));
+                    }
+
+                    let etag = response.headers().get_one("ETag").map(|etag| etag.to_string());
+
+                    let body = response.into_bytes().await.unwrap_or_default();
+
+                    let etag = etag.unwrap_or_else(|| compute_data_etag(&body).to_string());
+
+                    (body, etag)
+                },
+            };
+
+            write_file(&self.output_dir.join(&file), &content)?;
+
+            manifest.insert(file, etag);
+        }
+
+        write_file(
+            &self.output_dir.join(EXPORT_MANIFEST_NAME),
+            &serde_json::to_vec_pretty(&manifest)?,
+        )?;
+
+        Ok(manifest)
+    }
+}
+
+#[inline]
+fn other_error<E: ToString>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
+
+#[inline]
+fn write_file(path: &Path, content: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, content)
+}
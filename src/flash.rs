@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use rocket::{
+    futures::FutureExt,
+    request::FlashMessage,
+    response::{Flash, Responder},
+    Request,
+};
+use serde::Serialize;
+use tera::{Context, Error as TeraError};
+
+use crate::{functions::compute_data_etag, CacheControl, EntityTag};
+
+/// The name of the variable of templates which holds the flash message.
+const FLASH_VARIABLE: &str = "flash";
+
+/// Render the HTML of a page from a context with the template set, the template and the locales chosen by a `TeraContextManager`.
+pub(crate) type PageRenderer = Arc<dyn Fn(&Context) -> Result<String, TeraError> + Send + Sync>;
+
+/// The context of a page and how it is rendered, so that the page is rendered when its response is sent, with the flash message of the request if there is one.
+#[derive(Educe, Clone)]
+#[educe(Debug)]
+pub(crate) struct PageSource {
+    name:     Arc<str>,
+    context:  Arc<Context>,
+    minify:   bool,
+    #[educe(Debug(ignore))]
+    renderer: PageRenderer,
+}
+
+impl PageSource {
+    #[inline]
+    pub(crate) fn new(
+        name: Arc<str>,
+        context: Context,
+        minify: bool,
+        renderer: PageRenderer,
+    ) -> PageSource {
+        PageSource {
+            name,
+            context: Arc::new(context),
+            minify,
+            renderer,
+        }
+    }
+
+    /// The name of the template, e.g. for logging.
+    #[inline]
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Render the HTML, with the flash message as `flash` if there is one.
+    #[inline]
+    pub(crate) fn render_html(&self, flash: Option<&FlashValue>) -> Result<String, TeraError> {
+        match flash {
+            Some(flash) => {
+                let mut context = Context::clone(&self.context);
+
+                flash.insert_into(&mut context);
+
+                (self.renderer)(&context)
+            },
+            None => (self.renderer)(&self.context),
+        }
+    }
+
+    /// Render the HTML as `render_html` does, and compute its ETag before it is minified.
+    pub(crate) fn render(
+        &self,
+        flash: Option<&FlashValue>,
+    ) -> Result<(String, EntityTag<'static>), TeraError> {
+        let html = self.render_html(flash)?;
+
+        let etag = compute_data_etag(html.as_bytes());
+
+        let html = if self.minify { html_minifier::minify(html).unwrap() } else { html };
+
+        Ok((html, etag))
+    }
+
+    /// The length of the context serialized as JSON, which approximates the memory it holds.
+    #[cfg(not(debug_assertions))]
+    #[inline]
+    pub(crate) fn weight(&self) -> usize {
+        serde_json::to_vec(&Context::clone(&self.context).into_json())
+            .map(|json| json.len())
+            .unwrap_or(0)
+    }
+}
+
+/// A flash message taken from a request, whose `kind` and `message` can be used by templates.
+#[derive(Debug, Serialize)]
+pub(crate) struct FlashValue {
+    kind:    String,
+    message: String,
+}
+
+impl FlashValue {
+    /// Take the flash message of a request. It is consumed, so its cookie is removed by the response.
+    #[inline]
+    pub(crate) fn take(request: &Request<'_>) -> Option<FlashValue> {
+        // the request guard of flash messages only reads the cookies, so it is always ready
+        let (kind, message) =
+            request.guard::<FlashMessage<'_>>().now_or_never()?.succeeded()?.into_inner();
+
+        Some(FlashValue {
+            kind,
+            message,
+        })
+    }
+
+    /// Give the flash message back to the client, so that the next page shows it, e.g. when the page which took it fails to render it.
+    #[inline]
+    pub(crate) fn restore(self, request: &Request<'_>) {
+        // the flash cookie is added by the responder, and the empty response is not used
+        let _ = Flash::new((), self.kind, self.message).respond_to(request);
+    }
+
+    #[inline]
+    pub(crate) fn insert_into(&self, context: &mut Context) {
+        context.insert(FLASH_VARIABLE, self);
+    }
+}
+
+/// The policy of the `Cache-Control` header of a page with a flash message, which is only for its request.
+#[inline]
+pub(crate) fn flash_cache_control() -> CacheControl {
+    CacheControl::custom("private, no-store")
+}
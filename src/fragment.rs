@@ -0,0 +1,164 @@
+use std::{
+    convert::Infallible,
+    marker::PhantomData,
+    sync::{Arc, OnceLock, PoisonError, RwLock},
+};
+
+use rocket::{
+    outcome::Outcome,
+    request::{self, FromRequest, Request},
+};
+use tera::{Context, Error as TeraError, Tera};
+
+use crate::{functions::generate_random_token, TeraContextManager};
+
+/// The separator between the name of a template and the name of its block, e.g. `index#content`.
+const BLOCK_SEPARATOR: char = '#';
+
+/// The default request header which asks for a fragment instead of the whole page.
+pub(crate) const DEFAULT_FRAGMENT_HEADER: &str = "HX-Request";
+
+/// The text rendered around a block, so that it can be cut out of the page. It is random for each process, so that it cannot be injected into contexts.
+#[inline]
+fn block_marker() -> &'static str {
+    static MARKER: OnceLock<String> = OnceLock::new();
+
+    MARKER.get_or_init(generate_random_token)
+}
+
+/// The name of the template of a block. It ends with the name (or the path) of the template, so that it is autoescaped as the template is.
+#[inline]
+fn block_template_name(name: &str, block: &str, escape_name: &str) -> String {
+    format!("{}{}{}{}{}", name, BLOCK_SEPARATOR, block, BLOCK_SEPARATOR, escape_name)
+}
+
+/// The source of the template of a block, which extends the template and marks where the block is rendered, so that `super()` and the other blocks work as they do in the page.
+fn block_template_source(name: &str, block: &str) -> Result<String, TeraError> {
+    if block.is_empty() || !block.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(TeraError::msg(format!("`{}` is not a valid name of blocks.", block)));
+    }
+
+    let quote =
+        ['"', '\'', '`'].into_iter().find(|quote| !name.contains(*quote)).ok_or_else(|| {
+            TeraError::msg(format!(
+                "The template `{}` cannot be extended by the template of a block.",
+                name
+            ))
+        })?;
+
+    let marker = block_marker();
+
+    Ok(format!(
+        "{{% extends {q}{}{q} %}}{{% block {b} %}}{m}{{{{ super() }}}}{m}{{% endblock {b} %}}",
+        name,
+        q = quote,
+        b = block,
+        m = marker,
+    ))
+}
+
+/// The templates of the blocks of a template set, so that a single block can be rendered with the inheritance chain of its template (including `super()`). The template of a block extends its template and is registered the first time the block is rendered, into a copy of the set instead of the set itself, so that it never shows up in it, e.g. in `Tera::get_template_names`. Build it again after the templates of the set are changed.
+///
+/// Tera has no public API to render a block, so the whole page is rendered by the template of the block and the block is cut out of it. A block which the page does not render, e.g. in a false `if`, cannot be rendered either.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BlockTemplates {
+    tera: Arc<RwLock<Tera>>,
+}
+
+impl BlockTemplates {
+    /// Build the templates of the blocks of a template set.
+    #[inline]
+    pub(crate) fn new(tera: &Tera) -> BlockTemplates {
+        BlockTemplates {
+            tera: Arc::new(RwLock::new(tera.clone()))
+        }
+    }
+
+    /// Render only a block of a template. `escape_name` is the path of the template if it is loaded from a file, or its name, which decides whether it is autoescaped.
+    pub(crate) fn render(
+        &self,
+        name: &str,
+        block: &str,
+        escape_name: &str,
+        context: &Context,
+    ) -> Result<String, TeraError> {
+        let block_name = block_template_name(name, block, escape_name);
+
+        let registered = self
+            .tera
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_template(&block_name)
+            .is_ok();
+
+        if !registered {
+            let source = block_template_source(name, block)?;
+
+            let mut tera = self.tera.write().unwrap_or_else(PoisonError::into_inner);
+
+            // a template which fails to be registered would be kept in the set, so check its parent first
+            tera.get_template(name)?;
+
+            tera.add_raw_template(&block_name, &source)?;
+        }
+
+        let html = self
+            .tera
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .render(&block_name, context)?;
+
+        cut_block(html).ok_or_else(|| {
+            TeraError::msg(format!(
+                "The block `{}` of the template `{}` is not rendered.",
+                block, name
+            ))
+        })
+    }
+}
+
+/// Cut the first block marked by `block_marker` out of a page.
+fn cut_block(mut html: String) -> Option<String> {
+    let marker = block_marker();
+
+    let start = html.find(marker)? + marker.len();
+
+    let end = start + html[start..].find(marker)?;
+
+    html.truncate(end);
+    html.drain(..start);
+
+    Some(html)
+}
+
+/// A request guard which tells whether a request asks for a fragment of a page, e.g. by htmx with the `HX-Request` header. The header is configured by the `fragment_header` method of the fairing of `TeraResponse`. The type parameter is the marker of the `TeraContextManager`.
+#[derive(Debug, Clone, Copy)]
+pub struct FragmentRequest<T = ()> {
+    requested: bool,
+    marker:    PhantomData<fn() -> T>,
+}
+
+impl<T> FragmentRequest<T> {
+    /// Whether a fragment is requested.
+    #[inline]
+    pub fn is_requested(&self) -> bool {
+        self.requested
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T: 'static> FromRequest<'r> for FragmentRequest<T> {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let requested = match req.rocket().state::<TeraContextManager<T>>() {
+            Some(cm) => req.headers().contains(cm.fragment_header.as_ref()),
+            None => req.headers().contains(DEFAULT_FRAGMENT_HEADER),
+        };
+
+        Outcome::Success(FragmentRequest {
+            requested,
+            marker: PhantomData,
+        })
+    }
+}
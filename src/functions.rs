@@ -1,6 +1,116 @@
-use crate::EntityTag;
+use std::{fmt::Write, panic};
+
+use rocket::{
+    request::Request,
+    tokio::{
+        runtime::{Handle, RuntimeFlavor},
+        task,
+    },
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{EntityTag, EtagIfNoneMatch};
 
 #[inline]
 pub(crate) fn compute_data_etag<B: AsRef<[u8]> + ?Sized>(data: &B) -> EntityTag<'static> {
     EntityTag::from_data(data)
 }
+
+/// Derive a cache key from the name of a template, its context and whether it is minified. The context is converted to a JSON value first, so that the order of the entries of maps does not matter, and the key contains it in full, so that different contexts never share a key.
+#[doc(hidden)]
+pub fn auto_cache_key<S: AsRef<str>, V: Serialize + ?Sized>(
+    name: S,
+    context: &V,
+    minify: bool,
+) -> Result<String, serde_json::Error> {
+    let context = serde_json::to_value(context)?;
+
+    Ok(Value::Array(vec![Value::from(name.as_ref()), Value::from(minify), context]).to_string())
+}
+
+/// Compute an ETag from the version of the templates, the name of a template, whether it is minified and its context, so that it can be validated without rendering.
+pub(crate) fn compute_context_etag<V: Serialize + ?Sized>(
+    template_version: &str,
+    name: &str,
+    minify: bool,
+    context: &V,
+) -> EntityTag<'static> {
+    let context = serde_json::to_value(context).unwrap().to_string();
+
+    compute_data_etag(
+        format!("{}\0{}\0{}\0{}", template_version, name, u8::from(minify), context).as_bytes(),
+    )
+}
+
+/// Run a closure on the blocking thread pool and propagate its panic.
+pub(crate) async fn spawn_blocking<F: FnOnce() -> T + Send + 'static, T: Send + 'static>(
+    f: F,
+) -> T {
+    task::spawn_blocking(f).await.unwrap_or_else(|err| panic::resume_unwind(err.into_panic()))
+}
+
+/// Run blocking work on the current thread. On a multi-threaded runtime, the runtime is told to move its other tasks to other threads first, so that they are not blocked by it.
+pub(crate) fn block_in_place<F: FnOnce() -> T, T>(f: F) -> T {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            task::block_in_place(f)
+        },
+        _ => f(),
+    }
+}
+
+/// Get the `If-None-Match` header of a request without going through its request guard.
+#[inline]
+pub(crate) fn etag_if_none_match<'r>(request: &'r Request<'_>) -> EtagIfNoneMatch<'r> {
+    EtagIfNoneMatch {
+        etag: request
+            .headers()
+            .get_one("If-None-Match")
+            .and_then(|etag| EntityTag::from_str(etag).ok()),
+    }
+}
+
+/// Compute a fingerprint of the data which can be used in URLs and file names.
+#[cfg(not(debug_assertions))]
+pub(crate) fn compute_data_fingerprint<B: AsRef<[u8]> + ?Sized>(data: &B) -> String {
+    compute_data_etag(data)
+        .get_tag()
+        .chars()
+        .map(|c| match c {
+            '+' => '-',
+            '/' => '_',
+            _ => c,
+        })
+        .collect()
+}
+
+/// Insert a URL-safe fingerprint of the data before the extension of the file name, e.g. `css/app.css` becomes `css/app.<fingerprint>.css`.
+#[cfg(not(debug_assertions))]
+pub(crate) fn fingerprint_asset_name<B: AsRef<[u8]> + ?Sized>(name: &str, data: &B) -> String {
+    let fingerprint = compute_data_fingerprint(data);
+
+    let file_name_start = name.rfind('/').map(|i| i + 1).unwrap_or(0);
+
+    match name[file_name_start..].rfind('.') {
+        Some(i) if i > 0 => {
+            let i = file_name_start + i;
+
+            format!("{}.{}{}", &name[..i], fingerprint, &name[i..])
+        },
+        _ => format!("{}.{}", name, fingerprint),
+    }
+}
+
+/// Generate a random token of 128 bits encoded in hex, e.g. for a nonce.
+pub(crate) fn generate_random_token() -> String {
+    let bytes: [u8; 16] = rand::random();
+
+    let mut token = String::with_capacity(bytes.len() * 2);
+
+    for byte in bytes {
+        write!(token, "{:02x}", byte).unwrap();
+    }
+
+    token
+}
@@ -0,0 +1,79 @@
+use std::{
+    convert::Infallible,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use rocket::{
+    http::Method,
+    outcome::Outcome,
+    request::{self, FromRequest, Request},
+    tokio::io::{AsyncRead, AsyncSeek, ReadBuf},
+};
+
+/// Rocket routes a `HEAD` request to `GET` routes by changing its method, so the original method is kept in the local cache of the request.
+struct HeadMark(bool);
+
+/// Remember whether a request is a `HEAD` request. It is called by the fairing of `TeraResponse` before the request is routed.
+#[inline]
+pub(crate) fn mark_head_request(req: &Request<'_>) {
+    req.local_cache(|| HeadMark(req.method() == Method::Head));
+}
+
+/// Whether a request is a `HEAD` request, including one which is automatically handled by a `GET` route.
+#[inline]
+pub(crate) fn is_head_request(req: &Request<'_>) -> bool {
+    req.method() == Method::Head || req.local_cache(|| HeadMark(false)).0
+}
+
+/// A request guard which tells whether a request is a `HEAD` request, including one which is automatically handled by a `GET` route. Use it with the `build_head` method of `TeraContextManager` to respond the headers without minifying.
+#[derive(Debug, Clone, Copy)]
+pub struct HeadRequest {
+    head: bool,
+}
+
+impl HeadRequest {
+    /// Whether the request is a `HEAD` request.
+    #[inline]
+    pub fn is_head(&self) -> bool {
+        self.head
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for HeadRequest {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        Outcome::Success(HeadRequest {
+            head: is_head_request(req)
+        })
+    }
+}
+
+/// An empty body whose size cannot be computed, so that no `Content-Length` header is sent with it. Rocket computes the size of a body by seeking it.
+pub(crate) struct UnknownSizeBody;
+
+impl AsyncRead for UnknownSizeBody {
+    #[inline]
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for UnknownSizeBody {
+    #[inline]
+    fn start_seek(self: Pin<&mut Self>, _position: io::SeekFrom) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "the size of the body is unknown"))
+    }
+
+    #[inline]
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(0))
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::HashMap,
+    convert::Infallible,
+    ops::Deref,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use rocket::{
+    outcome::Outcome,
+    request::{self, FromRequest, Request},
+};
+use serde_json::Value;
+use tera::{Error as TeraError, Function};
+
+thread_local! {
+    static LOCALES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Normalize a locale tag to its canonical case, e.g. `de_ch` and `DE-ch` become `de-CH` and `zh-hant-tw` becomes `zh-Hant-TW`.
+pub(crate) fn normalize_locale(locale: &str) -> String {
+    let mut normalized = String::with_capacity(locale.len());
+
+    for (i, subtag) in locale.trim().split(['-', '_']).enumerate() {
+        if i > 0 {
+            normalized.push('-');
+
+            match subtag.len() {
+                2 => {
+                    normalized.push_str(&subtag.to_ascii_uppercase());
+
+                    continue;
+                },
+                4 => {
+                    let (first, rest) = subtag.split_at(1);
+
+                    normalized.push_str(&first.to_ascii_uppercase());
+                    normalized.push_str(&rest.to_ascii_lowercase());
+
+                    continue;
+                },
+                _ => (),
+            }
+        }
+
+        normalized.push_str(&subtag.to_ascii_lowercase());
+    }
+
+    normalized
+}
+
+/// Expand locales sorted by preference with their primary language subtags, e.g. `["de-CH", "en"]` becomes `["de-CH", "de", "en"]`. The locales are normalized by `normalize_locale`.
+pub(crate) fn expand_locales<L: AsRef<str>>(locales: &[L]) -> Vec<String> {
+    let mut expanded: Vec<String> = Vec::with_capacity(locales.len() * 2);
+
+    for locale in locales {
+        let locale = normalize_locale(locale.as_ref());
+
+        let mut candidates = vec![locale.as_str()];
+
+        if let Some(primary) = locale.split('-').next() {
+            if primary.len() < locale.len() {
+                candidates.push(primary);
+            }
+        }
+
+        for candidate in candidates {
+            if !candidate.is_empty() && !expanded.iter().any(|e| e == candidate) {
+                expanded.push(candidate.to_string());
+            }
+        }
+    }
+
+    expanded
+}
+
+/// Find the name of the template localized for the first possible locale, e.g. `index.de` before `index`.
+pub(crate) fn resolve_localized_name<'a>(
+    locales: &[String],
+    name: &'a str,
+    exists: impl Fn(&str) -> bool,
+) -> Cow<'a, str> {
+    for locale in locales {
+        let localized_name = format!("{}.{}", name, locale);
+
+        if exists(&localized_name) {
+            return Cow::Owned(localized_name);
+        }
+    }
+
+    Cow::Borrowed(name)
+}
+
+/// Make the `t` function of templates rendered on the current thread use the given locales until the scope is dropped.
+pub(crate) struct LocaleScope;
+
+impl LocaleScope {
+    #[inline]
+    pub(crate) fn enter(locales: Vec<String>) -> LocaleScope {
+        LOCALES.with(|l| *l.borrow_mut() = locales);
+
+        LocaleScope
+    }
+}
+
+impl Drop for LocaleScope {
+    #[inline]
+    fn drop(&mut self) {
+        LOCALES.with(|l| l.borrow_mut().clear());
+    }
+}
+
+/// Message catalogs which can be registered as the `t` function of Tera, e.g. `{{ t(key="greeting", name=user.name) }}`. A catalog is a JSON object whose values are messages or nested objects (flattened into dotted keys). Placeholders like `{name}` in a message are substituted by the other arguments of the function. The `lang` argument can be used to choose a locale explicitly.
+#[derive(Debug, Clone, Default)]
+pub struct TeraCatalogs {
+    catalogs: Arc<RwLock<HashMap<String, HashMap<String, String>>>>,
+}
+
+impl TeraCatalogs {
+    /// Create an instance of `TeraCatalogs`.
+    #[inline]
+    pub fn new() -> TeraCatalogs {
+        TeraCatalogs::default()
+    }
+
+    /// Add or replace the catalog of a locale from a JSON string. The locale tag is case-insensitive and `_` can be used instead of `-`.
+    pub fn add_raw_catalog<L: AsRef<str>, S: AsRef<str>>(
+        &self,
+        locale: L,
+        content: S,
+    ) -> Result<(), TeraError> {
+        let value: Value = serde_json::from_str(content.as_ref()).map_err(TeraError::json)?;
+
+        let mut messages = HashMap::new();
+
+        flatten_messages(&mut messages, String::new(), value)?;
+
+        self.catalogs
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(normalize_locale(locale.as_ref()), messages);
+
+        Ok(())
+    }
+
+    /// Translate a key with the first possible locale of the given locales sorted by preference.
+    pub fn translate<L: AsRef<str>, K: AsRef<str>>(&self, locales: &[L], key: K) -> Option<String> {
+        let catalogs = self.catalogs.read().unwrap_or_else(PoisonError::into_inner);
+
+        expand_locales(locales)
+            .iter()
+            .find_map(|locale| catalogs.get(locale).and_then(|messages| messages.get(key.as_ref())))
+            .cloned()
+    }
+}
+
+fn flatten_messages(
+    messages: &mut HashMap<String, String>,
+    prefix: String,
+    value: Value,
+) -> Result<(), TeraError> {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() { key } else { format!("{}.{}", prefix, key) };
+
+                flatten_messages(messages, key, value)?;
+            }
+        },
+        Value::String(message) if !prefix.is_empty() => {
+            messages.insert(prefix, message);
+        },
+        _ => {
+            return Err(TeraError::msg(format!(
+                "The catalog entry `{}` should be a string or an object.",
+                prefix
+            )))
+        },
+    }
+
+    Ok(())
+}
+
+impl Function for TeraCatalogs {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        let key = match args.get("key") {
+            Some(Value::String(key)) => key,
+            _ => return Err(TeraError::msg("The `t` function needs a `key` string argument.")),
+        };
+
+        let message = match args.get("lang") {
+            Some(Value::String(lang)) => self.translate(&[lang], key),
+            _ => LOCALES.with(|locales| self.translate(&locales.borrow(), key)),
+        };
+
+        let mut message = message.unwrap_or_else(|| key.clone());
+
+        for (name, value) in args {
+            if name == "key" || name == "lang" {
+                continue;
+            }
+
+            let placeholder = format!("{{{}}}", name);
+
+            if message.contains(&placeholder) {
+                let value = match value {
+                    Value::String(s) => Cow::Borrowed(s.as_str()),
+                    _ => Cow::Owned(value.to_string()),
+                };
+
+                message = message.replace(&placeholder, &value);
+            }
+        }
+
+        Ok(Value::String(message))
+    }
+}
+
+/// The languages in the `Accept-Language` header of a request, sorted by preference.
+#[derive(Debug, Clone, Default)]
+pub struct AcceptLanguage {
+    languages: Vec<String>,
+}
+
+impl AcceptLanguage {
+    /// Parse the value of an `Accept-Language` header.
+    pub fn parse<S: AsRef<str>>(s: S) -> AcceptLanguage {
+        let mut languages: Vec<(String, f32)> = s
+            .as_ref()
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+
+                let language = parts.next()?.trim();
+
+                if language.is_empty() || language == "*" {
+                    return None;
+                }
+
+                let quality = parts
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .and_then(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+
+                if quality > 0.0 {
+                    Some((language.to_string(), quality))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        // stable, so languages with the same quality keep their order
+        languages.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        AcceptLanguage {
+            languages: languages.into_iter().map(|(language, _)| language).collect()
+        }
+    }
+}
+
+impl Deref for AcceptLanguage {
+    type Target = [String];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.languages
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AcceptLanguage {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        Outcome::Success(
+            req.headers().get_one("Accept-Language").map(AcceptLanguage::parse).unwrap_or_default(),
+        )
+    }
+}
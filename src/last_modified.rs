@@ -0,0 +1,67 @@
+use std::{
+    convert::Infallible,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rocket::{
+    http::Method,
+    outcome::Outcome,
+    request::{self, FromRequest, Request},
+};
+
+/// A request guard which gets the time in the `If-Modified-Since` header of a request. The header is ignored if the request also has the `If-None-Match` header, or if its method is neither `GET` nor `HEAD`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IfModifiedSince {
+    pub time: Option<SystemTime>,
+}
+
+impl IfModifiedSince {
+    #[inline]
+    pub(crate) fn from_request_headers(req: &Request<'_>) -> IfModifiedSince {
+        let time = if matches!(req.method(), Method::Get | Method::Head)
+            && !req.headers().contains("If-None-Match")
+        {
+            req.headers()
+                .get_one("If-Modified-Since")
+                .and_then(|time| httpdate::parse_http_date(time).ok())
+        } else {
+            None
+        };
+
+        IfModifiedSince {
+            time,
+        }
+    }
+
+    /// Whether a resource modified at the given time is newer than the time of the request. HTTP dates have a precision of one second, so the subsecond part of the time is ignored. If there is no time in the request, it is always `true`.
+    #[inline]
+    pub fn is_modified(&self, last_modified: SystemTime) -> bool {
+        match self.time {
+            Some(time) => truncate_to_seconds(last_modified) > time,
+            None => true,
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfModifiedSince {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        Outcome::Success(IfModifiedSince::from_request_headers(req))
+    }
+}
+
+#[inline]
+fn truncate_to_seconds(time: SystemTime) -> SystemTime {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => UNIX_EPOCH + Duration::from_secs(duration.as_secs()),
+        Err(_) => time,
+    }
+}
+
+/// Format a time as an HTTP date for the `Last-Modified` header.
+#[inline]
+pub(crate) fn format_http_date(time: SystemTime) -> String {
+    httpdate::fmt_http_date(time)
+}
@@ -5,8 +5,28 @@ This is a crate which provides macros `tera_resources_initialize!` and `tera_res
 
 * `tera_resources_initialize!` is used in the fairing of `TeraResponseFairing` to include Tera files into your executable binary file. You need to specify each file's name and its path relative to the directory containing the manifest of your package. In order to reduce the compilation time and allow to hot-reload templates, files are compiled into your executable binary file together, only when you are using the **release** profile.
 * `tera_response!` is used for retrieving and rendering the file you input through the macro `tera_resources_initialize!` as a `TeraResponse` instance with rendered HTML. When its `respond_to` method is called, three HTTP headers, **Content-Type**, **Content-Length** and **Etag**, will be automatically added, and the rendered HTML can optionally not be minified.
-* `tera_response_cache!` is used for wrapping a `TeraResponse` and its constructor, and use a **key** to cache its HTML and ETag in memory. The cache is generated only when you are using the **release** profile.
-* `tera_resources_initializer!` is used for generating a fairing for tera resources.
+* `tera_response_cache!` is used for wrapping a `TeraResponse` and its constructor, and use a **key** to cache its HTML and ETag in memory. The cache is generated only when you are using the **release** profile. Use `tera_response_cache!(auto cm, etag_if_none_match, "index", context)` to derive the key from the name of the template, its context and the minify flag instead.
+* Call `warm(key, name, context)` on a fairing to render a template into the cache under a key when the Rocket instance is ignited, so that the first request after a deploy hits the cache of `tera_response_cache!`. The launch is aborted if a warm-up entry cannot be rendered.
+* Use the `cache_stats` method of `TeraContextManager` to get the hits, misses, inserts, evictions, entries and bytes of the cache, and `cache_keys` to list its keys. Mount `TeraCacheStats` to expose the statistics as JSON, e.g. for an admin dashboard.
+* Call `cache_bytes(max_bytes)` on a fairing to limit the cache by a byte budget instead of its capacity in entries. Each entry weighs the length of its HTML plus its ETag and its context, which is kept to render the page with flash messages, and the least recently used entries are evicted until the cache is under the budget. The cache is not sharded then, so that a large page can use the whole budget.
+* Call `disk_cache(directory)` on a fairing to persist the cache into a directory as a second tier, which is consulted on in-memory misses and survives restarts. It is versioned by a hash of the executable binary file, which embeds the templates, the catalogs and the assets of the macros together with functions, filters and globals, the names of the templates and the URLs of the assets of the fairing, so that a new deploy never serves stale layouts. Templates and assets loaded from files at runtime are not hashed, so call `cache_version(version)` on the fairing and bump the version whenever they change. The directories of other versions are kept, so that the processes of the previous deploy keep working during a rolling deploy; remove them after it. Files are read with `block_in_place` and written on the blocking thread pool. It is cleared by `clear_cache` as well. The files are kept in a `rocket-include-tera` subdirectory per template set, so other files in the directory are never touched.
+* Use the `build_with_context_etag` method of `TeraContextManager` to compute the ETag from the version of the templates, the name of the template and its context instead of the rendered HTML, so that a matching `If-None-Match` is answered with **304 Not Modified** without rendering or minifying at all. In the **release** profile, the version is a hash of the version of the disk cache and the modification time of the executable binary file, so it changes with every deploy; in the **debug** profile, it changes whenever the templates are reloaded.
+* Responses of `tera_response_cache!` carry a **Last-Modified** timestamp, which is the newest modification time among the file of the template and the files of its parents in the **debug** profile, and the time when the executable binary file was built in the **release** profile. A request whose `If-Modified-Since` header is not older than the timestamp gets **304 Not Modified**, unless it also has an `If-None-Match` header. Other responses have no timestamp by default, because their contexts may change at any time. Call `last_modified` on a `TeraResponse` to set or remove the timestamp, and use the `IfModifiedSince` request guard with the `last_modified` method of `TeraContextManager` to skip rendering.
+* Call `cache_control(policy)` on a fairing to send a default **Cache-Control** header, such as `CacheControl::NoCache`, `CacheControl::Private { max_age }` or `CacheControl::Public { max_age, s_maxage, stale_while_revalidate }`, with the responses built by its `TeraContextManager`, including **304 Not Modified** ones. Call `cache_control` on a `TeraResponse` to override it, or use `tera_response_cache!(cache_control policy => cm, etag_if_none_match, key, { ... })` to override it for an entry of the cache. A policy set on the `TeraResponse` built in the block of `tera_response_cache!` is kept for its entry as well.
+* `HEAD` requests, which Rocket handles with `GET` routes, get **Content-Length** and **Etag** without copying the body, so a hit of `tera_response_cache!` in the **release** profile costs no rendering. For entries which are not cached, use the `HeadRequest` request guard with `tera_response!(head head_request => cm, etag_if_none_match, "index", context)` to compute the headers without minifying, and without **Content-Length**.
+* Use the `build_stream` method of `TeraContextManager` to stream a huge page with chunked transfer while the template is being rendered on the blocking thread pool, instead of holding the whole HTML in memory. The HTML is not minified, and no ETag is sent, because both need the whole HTML. If rendering fails in the middle, the response is aborted instead of ending like a complete page. Use `build_stream_with_context_etag` to send an ETag computed from the context as `build_with_context_etag` does.
+* Call `content_security_policy(policy)` on a fairing to send a **Content-Security-Policy** header with the HTML responses built by its `TeraContextManager`, e.g. `script-src 'nonce-{nonce}'`. Every `{nonce}` is replaced by a new random nonce for each response, which templates can use as `csp_nonce`. Templates are rendered with a placeholder of the nonce, which is replaced when a response is sent, so the entries of `tera_response_cache!` get fresh nonces without being rendered again. A **304 Not Modified** response carries no policy, so the browser keeps the page with the nonce and the policy it stored. The `render` methods of `TeraContextManager` and `StaticExport` return HTML with nonces which are not sent in any header, so that HTML is not protected by the policy.
+* With the `csrf` feature, call `csrf()` on a fairing to provide the CSRF token of each request to templates as `csrf_token`, together with a `csrf_field()` function which renders a hidden field of it. The token is kept in a private cookie of Rocket, so a `secret_key` needs to be configured in the **release** profile. Use the `CsrfForm<T>` data guard instead of `Form<T>` to respond **403 Forbidden** to submitted forms whose `csrf_token` field does not match (the token is checked before other fields are parsed, so put `csrf_field()` before the file fields of multipart forms), and the `CsrfToken` request guard to get the token, e.g. for scripts. Like `csp_nonce`, the token is substituted when a response is sent, so cached pages carry the token of their requests. Because the token is specific to a client, it is mixed into the ETag of HTML responses, which are sent with `Cache-Control: private, no-cache` and without `Last-Modified`, so a client whose cookie has expired never revalidates a page with its old token, and shared caches never hand the token of a client to others.
+* The flash message of a request is provided to the template of `tera_response!` as `flash`, whose `kind` and `message` can be used, e.g. `{% if flash %}{{ flash.message }}{% endif %}`. Pages are rendered when their responses are sent, so a page is rendered only once, with the flash message if its request carries one, whichever method of `TeraContextManager` builds it. A response of the cache of `tera_response_cache!` is rendered again with the flash message from the context of its entry, which is kept in memory but not in the disk cache, so an entry read from the disk cache leaves the flash message to the next page. If a page fails to be rendered with the flash message, it is sent without it, and the flash message is kept for the next page. The flash message is consumed, and the page which carries it is sent with `Cache-Control: private, no-store` and without **Last-Modified**.
+* `tera_catchers!` is used for creating Rocket catchers which render error pages with templates, e.g. `register("/", tera_catchers!("404" => "errors/404", default => "errors/generic"))`, whose codes are checked at compile time. The templates get `status`, `reason` and `path` of the request, together with the values provided by the fairing, such as `csp_nonce`, and error pages are sent with `Cache-Control: no-store`. If the `TeraContextManager` is not managed, or the template cannot be rendered, a plain text page of the status is responded instead. Use `TeraCatcher::new(code, name).tagged::<T>()` to render with another template set.
+* `tera_resources_initializer!` is used for generating a fairing for tera resources. Call `tagged::<T>()` on a fairing to manage its template set as `TeraContextManager<T>`, so that multiple template sets can be used in the same Rocket instance.
+* `#[derive(TeraTemplate)]` (with the default `derive` feature) is used for binding a context struct to its template, e.g. `#[template(name = "index", path = "views/index.tera")]`, so that the struct can be responded directly and its template is registered the first time it is responded. The variables used in the template itself (not in the templates which it extends or includes) are checked against the fields of the struct at compile time, except the ones listed in `provided(..)`, such as `csp_nonce`, `csrf_token` or `flash`, which are provided by the fairing. Add `tagged = T` to respond it with the `TeraContextManager` of a fairing which is `tagged::<T>()`.
+* `tera_catalogs_initialize!` is used in the fairing of `TeraResponseFairing` to include message catalogs of locales, which can be used through the `t` function of templates. Use the `build_localized` method of `TeraContextManager` with the `AcceptLanguage` request guard to render the template localized for the preferred locales of a client, e.g. `index.de` before `index`. Locale tags are case-insensitive and normalized to canonical tags like `de-CH`.
+* `tera_assets_initialize!` is used for registering static assets into a `TeraAssets` instance. Pass it to `assets` on a fairing to register the `asset` function of templates, e.g. `{{ asset(path="css/app.css") }}`, and mount it to serve the assets. In the **release** profile, assets are compiled into your executable binary file and their URLs are fingerprinted by their content so that they can be served with a long-lived **Cache-Control** header. The fingerprints are computed once when the assets are registered, because hashing them in the const evaluator would cost seconds of compilation time per megabyte. In the **debug** profile, their URLs carry the modification times of the files instead.
+* Call `overlay(name, callback)` on a fairing to add a named template set (e.g. a theme or a tenant) whose templates override the base ones. Use the `build_overlay` method of `TeraContextManager` to render with it, and `extends` and `include` are resolved against the overlay first. Rendering with an overlay which has not been added panics like rendering a template which is not registered. Overlays are embedded in the **release** profile and hot-reloaded in the **debug** profile like the base set.
+* Use the `build_block` method of `TeraContextManager` to render only a `{% block %}` of a template, e.g. for htmx or Turbo to update a part of a page. `tera_response!(fragment fragment_request => "content", cm, etag_if_none_match, "index", context)` renders only the block when the `FragmentRequest` request guard finds the request header configured by `fragment_header` on the fairing (`HX-Request` by default), and sends the header in **Vary**. The whole page is rendered with the context and the block is cut out of it, so templates added at runtime work as well. Template names should not contain `#`, which is used to name the templates of blocks.
+* Use the `build_negotiated` method of `TeraContextManager` with the `ResponseFormat` request guard to serve browsers and API clients from the same route. When `application/json` is preferred by the `Accept` header, the context is serialized as JSON instead of rendering the template, with the ETag computed from the JSON. `Accept` is sent in **Vary**, and JSON is never stored in the cache of `tera_response_cache!`.
+* `StaticExport` is used for pre-rendering templates with given contexts and routes (requested through `rocket::local`, without launching a server) into minified HTML files in an output directory, together with an `etags.json` manifest of their ETags, e.g. to serve marketing pages from a CDN.
 
 See `examples`.
 */
@@ -16,8 +36,28 @@ extern crate educe;
 
 #[doc(hidden)]
 pub extern crate manifest_dir_macros;
+#[doc(hidden)]
+pub extern crate rocket;
 
+mod cache_control;
+mod catcher;
+mod csp;
+#[cfg(feature = "csrf")]
+mod csrf;
+mod export;
+mod flash;
+mod fragment;
 mod functions;
+mod head;
+mod i18n;
+mod last_modified;
+mod negotiation;
+mod placeholder;
+mod stats;
+mod stream;
+mod template;
+mod tera_response;
+mod warm;
 
 #[cfg(debug_assertions)]
 mod debug;
@@ -27,10 +67,29 @@ mod release;
 
 mod macros;
 
+pub use cache_control::CacheControl;
+#[doc(hidden)]
+pub use catcher::parse_catcher_code;
+pub use catcher::TeraCatcher;
+#[cfg(feature = "csrf")]
+pub use csrf::{CsrfForm, CsrfToken};
 #[cfg(debug_assertions)]
 pub use debug::*;
+pub use export::{StaticExport, EXPORT_MANIFEST_NAME};
+pub use fragment::FragmentRequest;
+#[doc(hidden)]
+pub use functions::auto_cache_key;
+pub use head::HeadRequest;
+pub use i18n::{AcceptLanguage, TeraCatalogs};
+pub use last_modified::IfModifiedSince;
+pub use negotiation::ResponseFormat;
 #[cfg(not(debug_assertions))]
 pub use release::*;
 pub use rocket_etag_if_none_match::{entity_tag::EntityTag, EtagIfNoneMatch};
+#[cfg(feature = "derive")]
+pub use rocket_include_tera_derive::TeraTemplate;
+pub use stats::{CacheStats, TeraCacheStats};
+pub use template::TeraTemplate;
+pub use tera_response::TeraResponse;
 
 const DEFAULT_CACHE_CAPACITY: usize = 64;
@@ -1,6 +1,52 @@
-/// Used for retrieving and rendering the file you input through the macro `tera_resources_initialize!` as a `TeraResponse` instance with rendered HTML. When its `respond_to` method is called, three HTTP headers, **Content-Type**, **Content-Length** and **Etag**, will be automatically added, and the rendered HTML can optionally not be minified.
+/// Used for retrieving and rendering the file you input through the macro `tera_resources_initialize!` as a `TeraResponse` instance with rendered HTML. When its `respond_to` method is called, three HTTP headers, **Content-Type**, **Content-Length** and **Etag**, will be automatically added, and the rendered HTML can optionally not be minified. In async handlers, prefix the arguments with `async` to tell the runtime to move its other tasks to other threads while rendering. Prefix the arguments with `fragment $fragment_request => $block` to render only the block when the `FragmentRequest` request guard says a fragment is requested. Prefix the arguments with `head $head_request =>` to respond only the headers without minifying when the `HeadRequest` request guard says the request is a `HEAD` request. The HTML is rendered when the response is sent, with the flash message of the request as `flash` in the context if there is one.
 #[macro_export]
 macro_rules! tera_response {
+    ( async $cm:expr, $etag_if_none_match:expr, $name:expr ) => {
+        {
+            use ::std::collections::HashMap;
+
+            let map: HashMap<u8, u8> = HashMap::new();
+
+            $crate::tera_response!(async $cm, $etag_if_none_match, $name, map)
+        }
+    };
+    ( async $cm:expr, $etag_if_none_match:expr, $name:expr, $data:expr ) => {
+        $crate::tera_response!(async enable_minify $cm, $etag_if_none_match, $name, $data)
+    };
+    ( async enable_minify $cm:expr, $etag_if_none_match:expr, $name:expr ) => {
+        {
+            use ::std::collections::HashMap;
+
+            let map: HashMap<u8, u8> = HashMap::new();
+
+            $crate::tera_response!(async enable_minify $cm, $etag_if_none_match, $name, map)
+        }
+    };
+    ( async enable_minify $cm:expr, $etag_if_none_match:expr, $name:expr, $data:expr ) => {
+        $cm.build_async(
+            &$etag_if_none_match,
+            true,
+            $name,
+            &$data,
+        ).await
+    };
+    ( async disable_minify $cm:expr, $etag_if_none_match:expr, $name:expr ) => {
+        {
+            use ::std::collections::HashMap;
+
+            let map: HashMap<u8, u8> = HashMap::new();
+
+            $crate::tera_response!(async disable_minify $cm, $etag_if_none_match, $name, map)
+        }
+    };
+    ( async disable_minify $cm:expr, $etag_if_none_match:expr, $name:expr, $data:expr ) => {
+        $cm.build_async(
+            &$etag_if_none_match,
+            false,
+            $name,
+            &$data,
+        ).await
+    };
     ( $cm:expr, $etag_if_none_match:expr, $name:expr ) => {
         {
             use ::std::collections::HashMap;
@@ -63,6 +109,43 @@ macro_rules! tera_response {
             tera_response!(enable_minify $cm, $etag_if_none_match, $name, $data)
         }
     };
+    ( fragment $fragment_request:expr => $block:expr, $cm:expr, $etag_if_none_match:expr, $name:expr ) => {
+        {
+            use ::std::collections::HashMap;
+
+            let map: HashMap<u8, u8> = HashMap::new();
+
+            $crate::tera_response!(fragment $fragment_request => $block, $cm, $etag_if_none_match, $name, map)
+        }
+    };
+    ( fragment $fragment_request:expr => $block:expr, $cm:expr, $etag_if_none_match:expr, $name:expr, $data:expr ) => {
+        $cm.build_fragment(
+            &$fragment_request,
+            &$etag_if_none_match,
+            true,
+            $name,
+            $block,
+            &$data,
+        )
+    };
+    ( head $head_request:expr => $cm:expr, $etag_if_none_match:expr, $name:expr ) => {
+        {
+            use ::std::collections::HashMap;
+
+            let map: HashMap<u8, u8> = HashMap::new();
+
+            $crate::tera_response!(head $head_request => $cm, $etag_if_none_match, $name, map)
+        }
+    };
+    ( head $head_request:expr => $cm:expr, $etag_if_none_match:expr, $name:expr, $data:expr ) => {
+        $cm.build_head(
+            &$head_request,
+            &$etag_if_none_match,
+            true,
+            $name,
+            &$data,
+        )
+    };
 }
 
 /// Used for generating a fairing for tera resources.
@@ -91,3 +174,29 @@ macro_rules! tera_resources_initializer {
         }
     };
 }
+
+/// Used for creating Rocket catchers which render the error pages of status codes with templates of the default `TeraContextManager`, e.g. `tera_catchers!("404" => "errors/404", default => "errors/generic")`. A code is a string literal or an integer literal, which is checked at compile time, and `default` catches all errors which have no other catchers. The templates get `status`, `reason` and `path`. Register the catchers by `register("/", catchers)`.
+#[macro_export]
+macro_rules! tera_catchers {
+    ( @catcher default => $name:expr ) => {
+        $crate::TeraCatcher::new(::std::option::Option::None, $name)
+    };
+    ( @catcher $code:literal => $name:expr ) => {
+        {
+            const CODE: u16 = $crate::parse_catcher_code(::std::stringify!($code));
+
+            $crate::TeraCatcher::new(CODE, $name)
+        }
+    };
+    ( $($code:tt => $name:expr), * $(,)* ) => {
+        {
+            let catchers: ::std::vec::Vec<$crate::rocket::Catcher> = ::std::vec![
+                $(
+                    $crate::tera_catchers!(@catcher $code => $name).into(),
+                )*
+            ];
+
+            catchers
+        }
+    };
+}
@@ -0,0 +1,47 @@
+use std::convert::Infallible;
+
+use rocket::{
+    outcome::Outcome,
+    request::{self, FromRequest, Request},
+};
+use serde::Serialize;
+
+use crate::{functions::compute_data_etag, EtagIfNoneMatch, TeraResponse};
+
+/// The format of a response preferred by a client according to the `Accept` header of its request. JSON is chosen only if `application/json` is preferred, so browsers and requests without the header get HTML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResponseFormat {
+    #[default]
+    Html,
+    Json,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ResponseFormat {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let format = match req.accept() {
+            Some(accept) if accept.preferred().is_json() => ResponseFormat::Json,
+            _ => ResponseFormat::Html,
+        };
+
+        Outcome::Success(format)
+    }
+}
+
+/// Serialize a context to JSON as a `TeraResponse`, with the ETag computed from the JSON.
+pub(crate) fn build_from_json<V: Serialize>(
+    etag_if_none_match: &EtagIfNoneMatch<'_>,
+    context: V,
+) -> TeraResponse {
+    let json = serde_json::to_string(&context).unwrap();
+
+    let etag = compute_data_etag(json.as_bytes());
+
+    if etag_if_none_match.weak_eq(&etag) {
+        TeraResponse::not_modified()
+    } else {
+        TeraResponse::build_json_not_cache(json, &etag)
+    }
+}
@@ -0,0 +1,251 @@
+use std::{
+    fmt::Debug,
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context as TaskContext, Poll},
+};
+
+use rocket::{
+    request::Request,
+    response::Builder,
+    tokio::io::{AsyncRead, ReadBuf},
+};
+use tera::Context;
+
+use crate::{functions::compute_data_etag, CacheControl, EntityTag};
+
+/// Pairs of placeholders and the values which replace them in a response.
+pub(crate) type Replacements = Vec<(&'static str, String)>;
+
+/// A value which differs for each response, such as a nonce or a token, and is provided to templates as a variable.
+pub(crate) trait ResponseValue: Debug + Send + Sync {
+    /// The name of the variable of templates.
+    fn name(&self) -> &'static str;
+
+    /// The text rendered for the variable, which is replaced by the value when a response is sent. It is random for each process, so that it cannot be guessed and injected into contexts, and it is as long as the values, so that the length of the HTML is kept.
+    fn placeholder(&self) -> &'static str;
+
+    /// Get the value of a response, and send the headers which carry it.
+    fn generate(&self, request: &Request<'_>, response: &mut Builder<'_>) -> String;
+
+    /// Get the value for HTML which is rendered outside of a response, so no header carries it.
+    fn detached(&self) -> String;
+
+    /// Get the value of a request if it is specific to the client, e.g. a CSRF token, so that the response must be neither shared with other clients nor revalidated with other values.
+    #[inline]
+    fn client_value(&self, _request: &Request<'_>) -> Option<String> {
+        None
+    }
+}
+
+/// The values which differ for each response. Templates are rendered with placeholders of the values, which are replaced when a response is sent, so that the rendered HTML can be cached.
+#[derive(Debug, Clone)]
+pub(crate) struct Placeholders {
+    values: Vec<Arc<dyn ResponseValue>>,
+}
+
+impl Placeholders {
+    /// Create an instance of `Placeholders` without any value.
+    #[inline]
+    pub(crate) const fn new() -> Placeholders {
+        Placeholders {
+            values: Vec::new()
+        }
+    }
+
+    /// Add a value. A value with the same name is replaced.
+    #[inline]
+    pub(crate) fn add<V: ResponseValue + 'static>(&mut self, value: V) {
+        self.values.retain(|v| v.name() != value.name());
+
+        self.values.push(Arc::new(value));
+    }
+
+    /// Whether a value with the name is added.
+    #[cfg(feature = "csrf")]
+    #[inline]
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.values.iter().any(|value| value.name() == name)
+    }
+
+    /// Get the names and the placeholders of the values, e.g. to translate them in files which outlive the process.
+    #[cfg(not(debug_assertions))]
+    #[inline]
+    pub(crate) fn names_and_placeholders(&self) -> Vec<(&'static str, &'static str)> {
+        self.values.iter().map(|value| (value.name(), value.placeholder())).collect()
+    }
+
+    /// Provide the placeholders to templates.
+    #[inline]
+    pub(crate) fn insert_into(&self, context: &mut Context) {
+        for value in self.values.iter() {
+            context.insert(value.name(), value.placeholder());
+        }
+    }
+
+    /// Get the values of a response, and send the headers which carry them.
+    #[inline]
+    pub(crate) fn generate(
+        &self,
+        request: &Request<'_>,
+        response: &mut Builder<'_>,
+    ) -> Replacements {
+        self.values
+            .iter()
+            .map(|value| {
+                let placeholder = value.placeholder();
+
+                let generated = value.generate(request, response);
+
+                // the placeholders are replaced in place, so the length of the HTML must be kept
+                debug_assert_eq!(
+                    placeholder.len(),
+                    generated.len(),
+                    "The value of `{}` must be as long as its placeholder.",
+                    value.name()
+                );
+
+                (placeholder, generated)
+            })
+            .collect()
+    }
+
+    /// Get the values of a request which are specific to the client.
+    #[inline]
+    pub(crate) fn client_values(&self, request: &Request<'_>) -> Vec<String> {
+        self.values.iter().filter_map(|value| value.client_value(request)).collect()
+    }
+
+    /// Replace the placeholders in HTML which is rendered outside of a response, e.g. by the `render` method of `TeraContextManager`.
+    #[inline]
+    pub(crate) fn detach(&self, html: String) -> String {
+        if self.values.is_empty() {
+            return html;
+        }
+
+        let replacements = self
+            .values
+            .iter()
+            .map(|value| (value.placeholder(), value.detached()))
+            .collect::<Vec<_>>();
+
+        substitute(&html, &replacements)
+    }
+}
+
+/// Mix the values specific to a client into the ETag of a response, so that the client never revalidates HTML rendered with other values, e.g. after its CSRF cookie has expired.
+#[inline]
+pub(crate) fn mix_client_values(etag: &str, values: &[String]) -> EntityTag<'static> {
+    compute_data_etag(format!("{}\0{}", etag, values.join("\0")).as_bytes())
+}
+
+/// Get the policy of the `Cache-Control` header of a response with values specific to its client. It must not be stored by shared caches, and it must be revalidated, so that it is never reused with other values.
+#[inline]
+pub(crate) fn client_cache_control(cache_control: Option<CacheControl>) -> CacheControl {
+    match cache_control {
+        Some(CacheControl::NoStore) => CacheControl::NoStore,
+        _ => CacheControl::custom("private, no-cache"),
+    }
+}
+
+/// Replace the placeholders in rendered HTML with their values.
+#[inline]
+pub(crate) fn substitute(html: &str, replacements: &[(&'static str, String)]) -> String {
+    let mut html = html.to_string();
+
+    for (placeholder, value) in replacements {
+        html = html.replace(placeholder, value);
+    }
+
+    html
+}
+
+/// A body which replaces the placeholders in another body while it is being read. The bytes which may be the beginning of a placeholder are held until the next read.
+pub(crate) struct SubstitutingBody<R> {
+    inner:        R,
+    replacements: Replacements,
+    pending:      Vec<u8>,
+    eof:          bool,
+}
+
+impl<R> SubstitutingBody<R> {
+    #[inline]
+    pub(crate) fn new(inner: R, replacements: Replacements) -> SubstitutingBody<R> {
+        SubstitutingBody {
+            inner,
+            replacements,
+            pending: Vec::new(),
+            eof: false,
+        }
+    }
+
+    /// Replace the placeholders in the pending bytes. Values are as long as their placeholders, so they are replaced in place.
+    fn replace_pending(&mut self) {
+        for (placeholder, value) in self.replacements.iter() {
+            let placeholder = placeholder.as_bytes();
+
+            let mut i = 0;
+
+            while i + placeholder.len() <= self.pending.len() {
+                if &self.pending[i..(i + placeholder.len())] == placeholder {
+                    self.pending[i..(i + placeholder.len())].copy_from_slice(value.as_bytes());
+
+                    i += placeholder.len();
+                } else {
+                    i += 1;
+                }
+            }
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for SubstitutingBody<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        let held = this
+            .replacements
+            .iter()
+            .map(|(placeholder, _)| placeholder.len() - 1)
+            .max()
+            .unwrap_or_default();
+
+        loop {
+            let ready =
+                if this.eof { this.pending.len() } else { this.pending.len().saturating_sub(held) };
+
+            if ready > 0 || this.eof {
+                let n = ready.min(buf.remaining());
+
+                buf.put_slice(&this.pending[..n]);
+
+                this.pending.drain(..n);
+
+                return Poll::Ready(Ok(()));
+            }
+
+            let mut chunk = [0u8; 4096];
+
+            let mut chunk_buf = ReadBuf::new(&mut chunk);
+
+            match Pin::new(&mut this.inner).poll_read(cx, &mut chunk_buf) {
+                Poll::Ready(Ok(())) => {
+                    if chunk_buf.filled().is_empty() {
+                        this.eof = true;
+                    } else {
+                        this.pending.extend_from_slice(chunk_buf.filled());
+
+                        this.replace_pending();
+                    }
+                },
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
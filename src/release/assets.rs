@@ -0,0 +1,127 @@
+use std::{collections::HashMap, io::Cursor, sync::Arc};
+
+use rocket::{
+    data::Data,
+    http::{ContentType, Method, Status},
+    request::Request,
+    response::Response,
+    route::{Handler, Outcome, Route},
+};
+use tera::{Error as TeraError, Function, Value};
+
+use crate::functions::fingerprint_asset_name;
+
+#[derive(Debug, Clone)]
+struct Asset {
+    data:        &'static [u8],
+    fingerprint: String,
+}
+
+/// The name of the function of templates which gets the URL of an asset.
+pub(crate) const ASSET_FUNCTION: &str = "asset";
+
+/// Static assets which can be referenced from templates through the `asset` function, which is registered by the `assets` method of the fairing of `TeraResponse`, and served by mounting this instance. In the **release** profile, assets are embedded into the executable binary file and their URLs carry a fingerprint of their content, so they can be cached by browsers forever.
+#[derive(Debug, Clone)]
+pub struct TeraAssets {
+    base:         Arc<str>,
+    files:        Arc<HashMap<&'static str, Asset>>,
+    fingerprints: Arc<HashMap<String, &'static str>>,
+}
+
+impl TeraAssets {
+    /// Create an instance of `TeraAssets`. The `base` should be the path where this instance is going to be mounted.
+    #[inline]
+    pub fn new<S: AsRef<str>>(base: S) -> TeraAssets {
+        TeraAssets {
+            base:         Arc::from(base.as_ref().trim_end_matches('/')),
+            files:        Arc::new(HashMap::new()),
+            fingerprints: Arc::new(HashMap::new()),
+        }
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn register_asset_data(&mut self, name: &'static str, data: &'static [u8]) {
+        let name = name.trim_start_matches('/');
+        let fingerprint = fingerprint_asset_name(name, data);
+
+        Arc::make_mut(&mut self.fingerprints).insert(fingerprint.clone(), name);
+
+        if let Some(old_asset) = Arc::make_mut(&mut self.files).insert(name, Asset {
+            data,
+            fingerprint,
+        }) {
+            Arc::make_mut(&mut self.fingerprints).remove(&old_asset.fingerprint);
+        }
+    }
+
+    /// Get the URLs of all assets.
+    #[inline]
+    pub(crate) fn urls(&self) -> impl Iterator<Item = String> + '_ {
+        self.files.values().map(|asset| format!("{}/{}", self.base, asset.fingerprint))
+    }
+
+    /// Get the URL of an asset.
+    #[inline]
+    pub fn url<S: AsRef<str>>(&self, name: S) -> Option<String> {
+        let name = name.as_ref().trim_start_matches('/');
+
+        self.files.get(name).map(|asset| format!("{}/{}", self.base, asset.fingerprint))
+    }
+}
+
+impl Function for TeraAssets {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        match args.get("path") {
+            Some(Value::String(path)) => match self.url(path) {
+                Some(url) => Ok(Value::String(url)),
+                None => Err(TeraError::msg(format!("The asset `{}` is not registered.", path))),
+            },
+            _ => Err(TeraError::msg("The `asset` function needs a `path` string argument.")),
+        }
+    }
+
+    #[inline]
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+#[rocket::async_trait]
+impl Handler for TeraAssets {
+    async fn handle<'r>(&self, req: &'r Request<'_>, data: Data<'r>) -> Outcome<'r> {
+        let fingerprint = req.routed_segments(0..).collect::<Vec<&str>>().join("/");
+
+        let (name, asset) = match self
+            .fingerprints
+            .get(fingerprint.as_str())
+            .and_then(|name| self.files.get(name).map(|asset| (name, asset)))
+        {
+            Some(v) => v,
+            None => return Outcome::forward(data, Status::NotFound),
+        };
+
+        let mut response = Response::build();
+
+        if let Some(content_type) =
+            name.rsplit_once('.').and_then(|(_, extension)| ContentType::from_extension(extension))
+        {
+            response.header(content_type);
+        }
+
+        response.raw_header("Cache-Control", "public, max-age=31536000, immutable");
+        response.sized_body(asset.data.len(), Cursor::new(asset.data));
+
+        Outcome::Success(response.finalize())
+    }
+}
+
+impl From<TeraAssets> for Vec<Route> {
+    #[inline]
+    fn from(assets: TeraAssets) -> Self {
+        let mut route = Route::ranked(10, Method::Get, "/<path..>", assets);
+        route.name = Some("TeraAssets".into());
+
+        vec![route]
+    }
+}
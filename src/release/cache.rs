@@ -0,0 +1,280 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash, Hasher},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread,
+    time::Duration,
+};
+
+use lru_time_cache::LruCache;
+
+use super::disk_cache::DiskCache;
+use crate::{flash::PageSource, CacheControl, CacheStats, EntityTag};
+
+pub(crate) type CacheValue = (Arc<str>, Arc<EntityTag<'static>>);
+
+/// An entry of the cache.
+#[derive(Debug, Clone)]
+pub(crate) struct CacheEntry {
+    pub(crate) value:         CacheValue,
+    /// The policy of the `Cache-Control` header which overrides the default policy of the fairing for the entry, or `None` to use the default one. `Some(None)` removes the header.
+    pub(crate) cache_control: Option<Option<CacheControl>>,
+    /// The context of the page and how it is rendered, so that it can be rendered again with flash messages. It is kept only in memory.
+    pub(crate) source:        Option<PageSource>,
+}
+
+impl From<CacheValue> for CacheEntry {
+    #[inline]
+    fn from(value: CacheValue) -> Self {
+        CacheEntry {
+            value,
+            cache_control: None,
+            source: None,
+        }
+    }
+}
+
+/// The minimum number of entries of each shard, so that the LRU policy is still meaningful.
+const MIN_SHARD_CAPACITY: usize = 8;
+
+/// `LruCache` allocates for its whole capacity, so a cache limited by a byte budget is created with an expiry duration which never passes instead, which leaves the number of its entries unlimited.
+const NEVER_EXPIRE: Duration = Duration::from_secs(u32::MAX as u64);
+
+struct Shard {
+    entries:    LruCache<String, CacheEntry>,
+    capacity:   usize,
+    /// The total length of the HTML of the entries.
+    bytes:      usize,
+    /// The total weight of the entries.
+    weight:     usize,
+    max_weight: usize,
+}
+
+impl Shard {
+    #[inline]
+    fn remove(&mut self, key: &str) -> Option<CacheValue> {
+        let entry = self.entries.remove(key);
+
+        if let Some(entry) = entry.as_ref() {
+            self.bytes -= entry.value.0.len();
+            self.weight -= weight_of(entry);
+        }
+
+        entry.map(|entry| entry.value)
+    }
+
+    /// Remove the least recently used entry by ourselves, because `LruCache` drops it silently.
+    #[inline]
+    fn evict(&mut self) -> bool {
+        match self.entries.peek_iter().last().map(|(key, _)| key.clone()) {
+            Some(key) => self.remove(&key).is_some(),
+            None => false,
+        }
+    }
+}
+
+/// The weight of an entry in a byte budget, which is the length of its HTML plus its ETag, plus the length of its context serialized as JSON if it is kept.
+#[inline]
+fn weight_of(entry: &CacheEntry) -> usize {
+    entry.value.0.len()
+        + entry.value.1.get_tag().len()
+        + entry.source.as_ref().map(PageSource::weight).unwrap_or(0)
+}
+
+/// An LRU cache which is split into shards by the hashes of keys. Each shard has its own lock, so that accesses to different keys rarely contend with each other.
+pub(crate) struct ResponseCache {
+    hasher:    RandomState,
+    shards:    Box<[Mutex<Shard>]>,
+    hits:      AtomicU64,
+    misses:    AtomicU64,
+    inserts:   AtomicU64,
+    evictions: AtomicU64,
+    disk:      Option<DiskCache>,
+}
+
+impl ResponseCache {
+    /// Create a cache limited by a capacity in entries, or by a byte budget instead if it is given, optionally with a second-tier disk cache. If the number of shards is not given, it depends on the available parallelism and the capacity. A cache limited by a byte budget has only one shard, so that an entry can use the whole budget.
+    pub(crate) fn new(
+        capacity: usize,
+        shards: Option<usize>,
+        max_bytes: Option<usize>,
+        disk: Option<DiskCache>,
+    ) -> ResponseCache {
+        let shards = match max_bytes {
+            Some(max_bytes) => vec![Mutex::new(Shard {
+                entries:    LruCache::with_expiry_duration(NEVER_EXPIRE),
+                capacity:   usize::MAX,
+                bytes:      0,
+                weight:     0,
+                max_weight: max_bytes,
+            })],
+            None => {
+                let shards = shards
+                    .unwrap_or_else(|| {
+                        let parallelism =
+                            thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+
+                        parallelism.min(capacity / MIN_SHARD_CAPACITY)
+                    })
+                    .clamp(1, capacity.max(1));
+
+                (0..shards)
+                    .map(|i| {
+                        // distribute the remainder to the first shards so that the total capacity is exact
+                        let shard_capacity = capacity / shards + usize::from(i < capacity % shards);
+
+                        Mutex::new(Shard {
+                            entries:    LruCache::with_capacity(shard_capacity),
+                            capacity:   shard_capacity,
+                            bytes:      0,
+                            weight:     0,
+                            max_weight: usize::MAX,
+                        })
+                    })
+                    .collect()
+            },
+        };
+
+        ResponseCache {
+            hasher: RandomState::new(),
+            shards: shards.into_boxed_slice(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            inserts: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            disk,
+        }
+    }
+
+    #[inline]
+    fn shard(&self, key: &str) -> MutexGuard<'_, Shard> {
+        let index = if self.shards.len() == 1 {
+            0
+        } else {
+            let mut hasher = self.hasher.build_hasher();
+
+            key.hash(&mut hasher);
+
+            (hasher.finish() % self.shards.len() as u64) as usize
+        };
+
+        self.shards[index].lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<CacheEntry> {
+        let mut entry = self.shard(key).entries.get(key).cloned();
+
+        if entry.is_none() {
+            if let Some(disk) = self.disk.as_ref() {
+                entry = disk.get(key);
+
+                if let Some(entry) = entry.as_ref() {
+                    self.insert_into_memory(key.to_string(), entry.clone());
+                }
+            }
+        }
+
+        if entry.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+
+        entry
+    }
+
+    /// Check if a key exists in the memory or the disk without counting a hit or a miss.
+    #[inline]
+    pub(crate) fn contains_key(&self, key: &str) -> bool {
+        self.shard(key).entries.contains_key(key)
+            || self.disk.as_ref().map(|disk| disk.contains_key(key)).unwrap_or(false)
+    }
+
+    /// Insert an entry into the memory and the disk.
+    #[inline]
+    pub(crate) fn insert(&self, key: String, entry: CacheEntry) -> Option<CacheValue> {
+        if let Some(disk) = self.disk.as_ref() {
+            disk.insert(&key, &entry);
+        }
+
+        self.insert_into_memory(key, entry)
+    }
+
+    /// Insert an entry into the memory and evict the least recently used entries until the capacity or the byte budget is satisfied. An entry heavier than the byte budget is not cached.
+    fn insert_into_memory(&self, key: String, entry: CacheEntry) -> Option<CacheValue> {
+        let mut shard = self.shard(&key);
+
+        let old_value = shard.remove(&key);
+
+        let weight = weight_of(&entry);
+
+        if shard.capacity == 0 || weight > shard.max_weight {
+            return old_value;
+        }
+
+        while shard.entries.len() >= shard.capacity || shard.weight + weight > shard.max_weight {
+            if !shard.evict() {
+                break;
+            }
+
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+
+        shard.bytes += entry.value.0.len();
+        shard.weight += weight;
+        shard.entries.insert(key, entry);
+
+        self.inserts.fetch_add(1, Ordering::Relaxed);
+
+        old_value
+    }
+
+    #[inline]
+    pub(crate) fn clear(&self) {
+        for shard in self.shards.iter() {
+            let mut shard = shard.lock().unwrap_or_else(PoisonError::into_inner);
+
+            shard.entries.clear();
+            shard.bytes = 0;
+            shard.weight = 0;
+        }
+
+        if let Some(disk) = self.disk.as_ref() {
+            disk.clear();
+        }
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        let mut stats = CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            inserts: self.inserts.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            ..CacheStats::default()
+        };
+
+        for shard in self.shards.iter() {
+            let shard = shard.lock().unwrap_or_else(PoisonError::into_inner);
+
+            stats.entries += shard.entries.len();
+            stats.bytes += shard.bytes;
+        }
+
+        stats
+    }
+
+    /// Get the keys of all entries. The order is only meaningful within a shard.
+    pub(crate) fn keys(&self) -> Vec<String> {
+        self.shards
+            .iter()
+            .flat_map(|shard| {
+                let shard = shard.lock().unwrap_or_else(PoisonError::into_inner);
+
+                shard.entries.peek_iter().map(|(key, _)| key.clone()).collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
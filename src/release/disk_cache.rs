@@ -0,0 +1,243 @@
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use rocket::tokio::{fs as async_fs, runtime::Handle};
+use serde_json::{json, Value};
+use tera::Tera;
+
+use super::{cache::CacheEntry, TeraAssets};
+use crate::{
+    functions::{block_in_place, compute_data_fingerprint, generate_random_token},
+    placeholder::Placeholders,
+    CacheControl, EntityTag,
+};
+
+/// The directory in the directory given by users which contains the directories of all template sets, so that other files in it are never touched.
+const NAMESPACE_DIRECTORY: &str = "rocket-include-tera";
+
+/// The file in the directory of a template set which keeps the placeholders used in the files.
+const PLACEHOLDERS_FILE: &str = "placeholders.json";
+
+/// Compute a version of a template set from the names of its templates and the ones of its overlays, the URLs of its assets and the version given by users. The sources of the templates are not public in Tera, so the version of the disk cache adds the executable binary file, which embeds them.
+pub(crate) fn compute_template_version(
+    tera: &Tera,
+    overlays: &HashMap<String, Tera>,
+    assets: Option<&TeraAssets>,
+    cache_version: Option<&str>,
+) -> String {
+    let mut sources =
+        tera.get_template_names().map(|name| format!("template:{}", name)).collect::<Vec<_>>();
+
+    for (overlay_name, overlay) in overlays {
+        sources.extend(
+            overlay.get_template_names().map(|name| format!("overlay:{}:{}", overlay_name, name)),
+        );
+    }
+
+    if let Some(assets) = assets {
+        sources.extend(assets.urls().map(|url| format!("asset:{}", url)));
+    }
+
+    sources.sort();
+
+    if let Some(cache_version) = cache_version {
+        sources.push(cache_version.to_string());
+    }
+
+    // the format of the files is part of the version as well
+    sources.push(env!("CARGO_PKG_VERSION").to_string());
+
+    compute_data_fingerprint(&sources.join("\0"))
+}
+
+/// A second-tier cache which persists entries as files in `<directory>/rocket-include-tera/<tag>/<version>`, where the tag identifies the template set and the version comes from its templates and the executable binary file, so that entries rendered by other templates are never read. The directories of other versions are kept, so that the processes of another deploy which are still running, e.g. during a rolling deploy, can keep using them. Files are read on the current thread, which the runtime is told to leave, and written on the blocking thread pool. The contexts of entries are not stored, so entries read from files cannot show flash messages, which are left to the next page.
+pub(crate) struct DiskCache {
+    directory:    PathBuf,
+    /// The placeholders of a process are random, so they are replaced by the ones of the directory in the files.
+    placeholders: Vec<(&'static str, String)>,
+    /// Bumped by `clear`, so that the files of entries inserted before it are never written after it.
+    generation:   Arc<RwLock<u64>>,
+}
+
+impl DiskCache {
+    /// Create the directory of the version. Template sets with different tags can share the same base directory.
+    pub(crate) async fn new(
+        base_directory: &Path,
+        tag: &str,
+        version: &str,
+        values: &Placeholders,
+    ) -> io::Result<DiskCache> {
+        let set_directory =
+            base_directory.join(NAMESPACE_DIRECTORY).join(compute_data_fingerprint(tag));
+
+        let names_and_placeholders = values.names_and_placeholders();
+
+        // the executable binary file embeds the templates, the catalogs and the assets of the macros, and the placeholders of the values which are not provided are never translated, so they are part of the version as well
+        let executable = async_fs::read(env::current_exe()?).await?;
+
+        let version = compute_data_fingerprint(&format!(
+            "{}\0{}\0{}",
+            version,
+            compute_data_fingerprint(&executable),
+            names_and_placeholders.iter().map(|(name, _)| *name).collect::<Vec<_>>().join("\0")
+        ));
+
+        let directory = set_directory.join(&version);
+
+        async_fs::create_dir_all(&directory).await?;
+
+        let placeholders_path = set_directory.join(PLACEHOLDERS_FILE);
+
+        let mut stored: HashMap<String, String> = async_fs::read(&placeholders_path)
+            .await
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default();
+
+        let mut placeholders = Vec::new();
+        let mut changed = false;
+
+        for (name, placeholder) in names_and_placeholders {
+            let stored = stored.entry(name.to_string()).or_default();
+
+            // a placeholder is replaced in place, so it must be as long as the one of the process
+            if stored.len() != placeholder.len() {
+                *stored = generate_random_token();
+
+                changed = true;
+            }
+
+            placeholders.push((placeholder, stored.clone()));
+        }
+
+        if changed {
+            async_fs::write(&placeholders_path, serde_json::to_vec(&stored)?).await?;
+        }
+
+        Ok(DiskCache {
+            directory,
+            placeholders,
+            generation: Arc::new(RwLock::new(0)),
+        })
+    }
+
+    #[inline]
+    fn file_path(&self, key: &str) -> PathBuf {
+        self.directory.join(compute_data_fingerprint(key))
+    }
+
+    /// Check if the file of a key exists.
+    #[inline]
+    pub(crate) fn contains_key(&self, key: &str) -> bool {
+        let file_path = self.file_path(key);
+
+        block_in_place(|| file_path.is_file())
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<CacheEntry> {
+        let file_path = self.file_path(key);
+
+        let content = block_in_place(|| fs::read_to_string(file_path)).ok()?;
+
+        let entry: Value = match serde_json::from_str(&content) {
+            Ok(entry) => entry,
+            Err(err) => {
+                rocket::warn!("Failed to read the disk cache of `{}`: {}", key, err);
+
+                return None;
+            },
+        };
+
+        // the file name is a hash of the key, which may collide
+        if entry["key"].as_str() != Some(key) {
+            return None;
+        }
+
+        let etag = EntityTag::with_string(false, entry["etag"].as_str()?).ok()?;
+
+        let mut html = entry["html"].as_str()?.to_string();
+
+        for (placeholder, stored) in self.placeholders.iter() {
+            html = html.replace(stored, placeholder);
+        }
+
+        // the policy is kept as the value of the header, which is all that is sent
+        let cache_control = entry.get("cache_control").map(|cache_control| {
+            cache_control
+                .as_str()
+                .map(|cache_control| CacheControl::custom(cache_control.to_string()))
+        });
+
+        Some(CacheEntry {
+            value: (Arc::from(html), Arc::new(etag)),
+            cache_control,
+            source: None,
+        })
+    }
+
+    pub(crate) fn insert(&self, key: &str, entry: &CacheEntry) {
+        let file_path = self.file_path(key);
+
+        let mut html = entry.value.0.to_string();
+
+        for (placeholder, stored) in self.placeholders.iter() {
+            html = html.replace(placeholder, stored);
+        }
+
+        let mut content = json!({
+            "key": key,
+            "etag": entry.value.1.get_tag(),
+            "html": html,
+        });
+
+        if let Some(cache_control) = entry.cache_control.as_ref() {
+            content["cache_control"] = json!(cache_control.as_ref().map(ToString::to_string));
+        }
+
+        let content = serde_json::to_vec(&content).unwrap();
+
+        let key = key.to_string();
+        let generation = self.generation.clone();
+        let expected_generation = *generation.read().unwrap_or_else(PoisonError::into_inner);
+
+        let write = move || {
+            let generation = generation.read().unwrap_or_else(PoisonError::into_inner);
+
+            if *generation != expected_generation {
+                return;
+            }
+
+            // write a temporary file first, so that a partial file is never read, and name it uniquely, so that concurrent writers of the same key do not write into the same file
+            let temp_path = file_path.with_extension(format!("{}.tmp", generate_random_token()));
+
+            if let Err(err) =
+                fs::write(&temp_path, content).and_then(|_| fs::rename(&temp_path, &file_path))
+            {
+                rocket::warn!("Failed to write the disk cache of `{}`: {}", key, err);
+            }
+        };
+
+        match Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(write);
+            },
+            Err(_) => write(),
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        let mut generation = self.generation.write().unwrap_or_else(PoisonError::into_inner);
+
+        *generation += 1;
+
+        if let Err(err) = block_in_place(|| {
+            fs::remove_dir_all(&self.directory).and_then(|_| fs::create_dir_all(&self.directory))
+        }) {
+            rocket::warn!("Failed to clear the disk cache: {}", err);
+        }
+    }
+}
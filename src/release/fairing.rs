@@ -1,24 +1,178 @@
+use std::{
+    any::type_name, borrow::Cow, collections::HashMap, marker::PhantomData, path::PathBuf,
+    sync::Arc, time::UNIX_EPOCH,
+};
+
 use rocket::{
+    data::Data,
     fairing::{Fairing, Info, Kind},
+    request::Request,
     Build, Rocket,
 };
+use serde::Serialize;
 use tera::Tera;
 
-use super::{TeraContextManager, TeraResponse};
+use super::{
+    assets::ASSET_FUNCTION,
+    cache::{CacheEntry, ResponseCache},
+    disk_cache::{compute_template_version, DiskCache},
+    tera_response::build_time,
+    TeraAssets, TeraContextManager,
+};
+#[cfg(feature = "csrf")]
+use crate::csrf::{CsrfField, CsrfTokenValue, CSRF_FIELD_FUNCTION, CSRF_TOKEN_NAME};
+use crate::{
+    csp::CspNonce,
+    functions::{compute_data_etag, compute_data_fingerprint},
+    head::mark_head_request,
+    placeholder::Placeholders,
+    warm::WarmEntry,
+    CacheControl, TeraResponse,
+};
 
 const FAIRING_NAME: &str = "Tera";
 
-/// The fairing of `TeraResponse`.
-pub struct TeraResponseFairing {
+/// The fairing of `TeraResponse`. It manages a `TeraContextManager<T>`.
+pub struct TeraResponseFairing<T = ()> {
     pub(crate) custom_callback: Box<dyn Fn(&mut Tera) -> usize + Send + Sync + 'static>,
+    pub(crate) cache_shards:    Option<usize>,
+    pub(crate) cache_bytes:     Option<usize>,
+    pub(crate) disk_cache:      Option<PathBuf>,
+    pub(crate) cache_version:   Option<String>,
+    #[allow(clippy::type_complexity)]
+    pub(crate) overlays:        Vec<(String, Box<dyn Fn(&mut Tera) + Send + Sync + 'static>)>,
+    pub(crate) fragment_header: Cow<'static, str>,
+    pub(crate) warm_entries:    Vec<WarmEntry>,
+    pub(crate) cache_control:   Option<CacheControl>,
+    pub(crate) placeholders:    Placeholders,
+    pub(crate) assets:          Option<TeraAssets>,
+    pub(crate) marker:          PhantomData<fn() -> T>,
+}
+
+impl<T> TeraResponseFairing<T> {
+    /// Manage the `TeraContextManager` under another marker type, so that multiple template sets (e.g. for the public site and the admin panel), each with its own cache capacity and globals, can be used in the same Rocket instance. Use `&State<TeraContextManager<U>>` to get it.
+    #[inline]
+    pub fn tagged<U>(self) -> TeraResponseFairing<U> {
+        TeraResponseFairing {
+            custom_callback: self.custom_callback,
+            cache_shards:    self.cache_shards,
+            cache_bytes:     self.cache_bytes,
+            disk_cache:      self.disk_cache,
+            cache_version:   self.cache_version,
+            overlays:        self.overlays,
+            fragment_header: self.fragment_header,
+            warm_entries:    self.warm_entries,
+            cache_control:   self.cache_control,
+            placeholders:    self.placeholders,
+            assets:          self.assets,
+            marker:          PhantomData,
+        }
+    }
+
+    /// Add a named overlay, such as a theme or a tenant. The overlay starts with all templates of the base set, and templates registered by the callback override the ones with the same names. Use `TeraContextManager::build_overlay` to render with it.
+    #[inline]
+    pub fn overlay<S, F>(mut self, name: S, f: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&mut Tera) + Send + Sync + 'static, {
+        self.overlays.push((name.into(), Box::new(f)));
+
+        self
+    }
+
+    /// Set the request header which asks for a fragment of a page instead of the whole page. It is `HX-Request` by default. Use the `FragmentRequest` request guard to check it.
+    #[inline]
+    pub fn fragment_header<S: Into<Cow<'static, str>>>(mut self, header: S) -> Self {
+        self.fragment_header = header.into();
+
+        self
+    }
+
+    /// Set the default policy of the `Cache-Control` header of the responses built by the `TeraContextManager`. No `Cache-Control` header is sent by default. It can be overridden by the `cache_control` method of `TeraResponse`.
+    #[inline]
+    pub fn cache_control(mut self, cache_control: CacheControl) -> Self {
+        self.cache_control = Some(cache_control);
+
+        self
+    }
+
+    /// Set the policy of the `Content-Security-Policy` header of the HTML responses built by the `TeraContextManager`, e.g. `script-src 'nonce-{nonce}'`. Every `{nonce}` in the policy is replaced by a new nonce for each response, which is provided to templates as `csp_nonce`. Templates are rendered with a placeholder of the nonce, which is replaced when a response is sent, so responses of the cache of `tera_response_cache!` get new nonces as well. The ETag does not depend on the nonce, so a response of **304 Not Modified** carries no policy and the browser keeps using the page with the nonce and the policy it stored; a nonce is new for each page that is sent, not for each view. HTML rendered by the `render` methods of the `TeraContextManager` has new nonces which are not sent in any header.
+    #[inline]
+    pub fn content_security_policy<S: Into<String>>(mut self, policy: S) -> Self {
+        self.placeholders.add(CspNonce::new(policy));
+
+        self
+    }
+
+    /// Provide the CSRF token of each request to templates as `csrf_token`, and the `csrf_field()` function which renders a hidden field of it. The token is kept in a private cookie, which is set when a request has no valid one, so a `secret_key` needs to be configured in the **release** profile. Templates are rendered with a placeholder of the token, which is replaced when a response is sent, so responses of the cache of `tera_response_cache!` carry the token of their requests. The token is mixed into the ETag of HTML responses, which are sent with `Cache-Control: private, no-cache` and without `Last-Modified`. Use the `CsrfForm` data guard to validate submitted forms.
+    #[cfg(feature = "csrf")]
+    #[inline]
+    pub fn csrf(mut self) -> Self {
+        self.placeholders.add(CsrfTokenValue);
+
+        self
+    }
+
+    /// Register the `asset` function of templates, which gets the URL of an asset of the `TeraAssets`, e.g. `{{ asset(path="css/app.css") }}`. Mount the same `TeraAssets` to serve the assets.
+    #[inline]
+    pub fn assets(mut self, assets: TeraAssets) -> Self {
+        self.assets = Some(assets);
+
+        self
+    }
+
+    /// Render a template into the cache under a key when the Rocket instance is ignited, so that the first request of the key hits the cache of `tera_response_cache!`. The HTML is minified. The context is provided by the closure. If the template cannot be rendered, the launch is aborted.
+    #[inline]
+    pub fn warm<K, S, V, F>(mut self, key: K, name: S, f: F) -> Self
+    where
+        K: Into<String>,
+        S: Into<String>,
+        V: Serialize,
+        F: Fn() -> V + Send + Sync + 'static, {
+        self.warm_entries.push(WarmEntry::new(key.into(), name.into(), f));
+
+        self
+    }
+
+    /// Set the number of shards of the cache. Each shard is an independent LRU cache with its own lock, so that cache hits of different keys rarely contend with each other. Set it to `1` to use a single LRU cache. By default, it depends on the available parallelism and the cache capacity.
+    #[inline]
+    pub fn cache_shards(mut self, shards: usize) -> Self {
+        self.cache_shards = Some(shards);
+
+        self
+    }
+
+    /// Limit the cache by a byte budget instead of its capacity in entries. Each entry weighs the length of its HTML plus its ETag, and the least recently used entries are evicted until the cache is under the budget. An entry heavier than the whole budget is not cached. The cache has only one shard then, so `cache_shards` and the capacity are ignored.
+    #[inline]
+    pub fn cache_bytes(mut self, max_bytes: usize) -> Self {
+        self.cache_bytes = Some(max_bytes);
+
+        self
+    }
+
+    /// Persist the cache into a directory as a second tier, which is consulted when an entry is not in memory, so that the cache survives restarts. It is cleared by `clear_cache` as well. The entries are stored in `<directory>/rocket-include-tera/<tag>/<version>`, where the tag identifies the marker type of `tagged` and the version is computed from the executable binary file (which embeds the templates, the catalogs and the assets of the macros), the names of the templates, the URLs of the assets given to `assets`, and `cache_version`. The entries of other versions are kept, so that processes of another deploy can keep using them during a rolling deploy, and a new deploy never serves stale layouts. Other files in the directory are never touched, and template sets with different marker types can share it.
+    #[inline]
+    pub fn disk_cache<P: Into<PathBuf>>(mut self, directory: P) -> Self {
+        self.disk_cache = Some(directory.into());
+
+        self
+    }
+
+    /// Set a version which is part of the version of the templates. Templates and assets loaded from files at runtime are not hashed, so bump it whenever they change the rendered HTML, or the disk cache may serve stale entries after a deploy.
+    #[inline]
+    pub fn cache_version<S: Into<String>>(mut self, version: S) -> Self {
+        self.cache_version = Some(version.into());
+
+        self
+    }
 }
 
 #[rocket::async_trait]
-impl Fairing for TeraResponseFairing {
+impl<T: 'static> Fairing for TeraResponseFairing<T> {
     #[inline]
     fn info(&self) -> Info {
         Info {
-            name: FAIRING_NAME, kind: Kind::Ignite
+            name: FAIRING_NAME, kind: Kind::Ignite | Kind::Request
         }
     }
 
@@ -28,16 +182,108 @@ impl Fairing for TeraResponseFairing {
 
         let cache_capacity = (self.custom_callback)(&mut tera);
 
-        let state = TeraContextManager::new(tera, cache_capacity);
+        if let Some(assets) = self.assets.as_ref() {
+            tera.register_function(ASSET_FUNCTION, assets.clone());
+        }
+
+        #[cfg(feature = "csrf")]
+        if self.placeholders.contains(CSRF_TOKEN_NAME) {
+            tera.register_function(CSRF_FIELD_FUNCTION, CsrfField);
+        }
+
+        let overlays = self
+            .overlays
+            .iter()
+            .map(|(name, f)| {
+                let mut overlay = tera.clone();
+
+                f(&mut overlay);
+
+                (name.clone(), overlay)
+            })
+            .collect::<HashMap<_, _>>();
+
+        let version = compute_template_version(
+            &tera,
+            &overlays,
+            self.assets.as_ref(),
+            self.cache_version.as_deref(),
+        );
+
+        let disk_cache = match self.disk_cache.as_ref() {
+            Some(directory) => {
+                match DiskCache::new(directory, type_name::<T>(), &version, &self.placeholders)
+                    .await
+                {
+                    Ok(disk_cache) => Some(disk_cache),
+                    Err(err) => {
+                        rocket::error!(
+                            "Failed to open the disk cache in `{}`: {}",
+                            directory.display(),
+                            err
+                        );
+
+                        return Err(rocket);
+                    },
+                }
+            },
+            None => None,
+        };
+
+        // the ETags computed from contexts depend on everything which affects rendering, including functions, filters and globals, which are compiled into the executable binary file
+        let render_version = compute_data_fingerprint(&format!(
+            "{}\0{:?}",
+            version,
+            build_time().duration_since(UNIX_EPOCH).unwrap_or_default()
+        ));
+
+        let cache_table =
+            ResponseCache::new(cache_capacity, self.cache_shards, self.cache_bytes, disk_cache);
+
+        let state = TeraContextManager::<T>::new(
+            tera,
+            overlays,
+            self.fragment_header.clone(),
+            cache_table,
+            render_version,
+            self.cache_control.clone(),
+            self.placeholders.clone(),
+        );
+
+        for entry in self.warm_entries.iter() {
+            match entry.render(&state.tera, &self.placeholders) {
+                Ok((html, context)) => {
+                    let etag = compute_data_etag(html.as_bytes());
+
+                    let html = html_minifier::minify(html).unwrap();
+
+                    state.insert_entry(entry.key.clone(), CacheEntry {
+                        value:         (Arc::from(html), Arc::new(etag)),
+                        cache_control: None,
+                        source:        Some(state.page_source(&entry.name, context, true)),
+                    });
+                },
+                Err(message) => {
+                    rocket::error!("{}", message);
+
+                    return Err(rocket);
+                },
+            }
+        }
 
         Ok(rocket.manage(state))
     }
+
+    #[inline]
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        mark_head_request(req);
+    }
 }
 
 impl TeraResponse {
     /// Create the fairing of `TeraResponse`.
     #[inline]
-    pub fn fairing<F>(f: F) -> impl Fairing
+    pub fn fairing<F>(f: F) -> TeraResponseFairing
     where
         F: Fn(&mut Tera) + Send + Sync + 'static, {
         let f = Box::new(f);
@@ -48,16 +294,38 @@ impl TeraResponse {
 
                 crate::DEFAULT_CACHE_CAPACITY
             }),
+            cache_shards:    None,
+            cache_bytes:     None,
+            disk_cache:      None,
+            cache_version:   None,
+            overlays:        Vec::new(),
+            fragment_header: Cow::Borrowed(crate::fragment::DEFAULT_FRAGMENT_HEADER),
+            warm_entries:    Vec::new(),
+            cache_control:   None,
+            placeholders:    Placeholders::new(),
+            assets:          None,
+            marker:          PhantomData,
         }
     }
 
     /// Create the fairing of `TeraResponse` and set the cache capacity.
     #[inline]
-    pub fn fairing_cache<F>(f: F) -> impl Fairing
+    pub fn fairing_cache<F>(f: F) -> TeraResponseFairing
     where
         F: Fn(&mut Tera) -> usize + Send + Sync + 'static, {
         TeraResponseFairing {
-            custom_callback: Box::new(f)
+            custom_callback: Box::new(f),
+            cache_shards:    None,
+            cache_bytes:     None,
+            disk_cache:      None,
+            cache_version:   None,
+            overlays:        Vec::new(),
+            fragment_header: Cow::Borrowed(crate::fragment::DEFAULT_FRAGMENT_HEADER),
+            warm_entries:    Vec::new(),
+            cache_control:   None,
+            placeholders:    Placeholders::new(),
+            assets:          None,
+            marker:          PhantomData,
         }
     }
 }
@@ -12,7 +12,59 @@ macro_rules! tera_resources_initialize {
                 if set.contains($name) {
                     panic!("The name `{}` is duplicated.", $name);
                 } else {
-                    $tera.add_raw_template($name, include_str!($crate::manifest_dir_macros::path!($path))).unwrap();
+                    let source = include_str!($crate::manifest_dir_macros::path!($path));
+
+                    $tera.add_raw_template($name, source).unwrap();
+
+                    set.insert($name);
+                }
+            )*
+        }
+    };
+}
+
+/// Used in the fairing of `TeraResponse` to include message catalogs of locales into your executable binary file, and register them as the `t` function of Tera. You need to specify each catalog's locale and its path relative to the directory containing the manifest of your package. In order to reduce the compilation time and allow to hot-reload catalogs, files are compiled into your executable binary file together, only when you are using the **release** profile.
+#[macro_export]
+macro_rules! tera_catalogs_initialize {
+    ( $tera:expr, $($locale:expr => $path:expr), * $(,)* ) => {
+        {
+            use ::std::collections::HashSet;
+
+            let mut set: HashSet<&str> = HashSet::new();
+
+            let catalogs = $crate::TeraCatalogs::new();
+
+            $(
+                if set.contains($locale) {
+                    panic!("The locale `{}` is duplicated.", $locale);
+                } else {
+                    let source = include_str!($crate::manifest_dir_macros::path!($path));
+
+                    catalogs.add_raw_catalog($locale, source).unwrap();
+
+                    set.insert($locale);
+                }
+            )*
+
+            $tera.register_function("t", catalogs);
+        }
+    };
+}
+
+/// Used for registering static assets into a `TeraAssets` instance. You need to specify each asset's name and its path relative to the directory containing the manifest of your package. Assets are compiled into your executable binary file together, only when you are using the **release** profile. The fingerprint of each asset is computed from the embedded data when it is registered, instead of at compile time, because the const evaluator of Rust would take seconds per megabyte to hash it.
+#[macro_export]
+macro_rules! tera_assets_initialize {
+    ( $assets:expr, $($name:expr => $path:expr), * $(,)* ) => {
+        {
+            use ::std::collections::HashSet;
+
+            let mut set: HashSet<&str> = HashSet::new();
+
+            $(
+                if set.contains($name) {
+                    panic!("The name `{}` is duplicated.", $name);
+                } else {
+                    $assets.register_asset_data($name, include_bytes!($crate::manifest_dir_macros::path!($path)));
 
                     set.insert($name);
                 }
@@ -21,23 +73,49 @@ macro_rules! tera_resources_initialize {
     };
 }
 
-/// Used for wrapping a `TeraResponse` and its constructor, and use a **key** to cache its HTML and ETag in memory. The cache is generated only when you are using the **release** profile.
+/// Used for wrapping a `TeraResponse` and its constructor, and use a **key** to cache its HTML and ETag in memory. The cache is generated only when you are using the **release** profile. Prefix the arguments with `auto` and give the name and the context of a template instead of a key and a constructor to derive the key from the name, the context and the minify flag, e.g. `tera_response_cache!(auto cm, etag_if_none_match, "index", context)`. Prefix the arguments with `cache_control policy =>` to override the default policy of the `Cache-Control` header for the entry, e.g. `tera_response_cache!(cache_control CacheControl::NoCache => cm, etag_if_none_match, "index", { ... })`. When the request carries a flash message, a response built by `tera_response!` is rendered again with it instead of being served from the cache.
 #[macro_export]
 macro_rules! tera_response_cache {
+    ( auto $cm:expr, $etag_if_none_match:expr, $name:expr, $data:expr ) => {
+        $crate::tera_response_cache!(auto enable_minify $cm, $etag_if_none_match, $name, $data)
+    };
+    ( auto enable_minify $cm:expr, $etag_if_none_match:expr, $name:expr, $data:expr ) => {
+        {
+            let data = &$data;
+
+            match $crate::auto_cache_key($name, data, true) {
+                ::std::result::Result::Ok(key) => $crate::tera_response_cache!($cm, $etag_if_none_match, key, {
+                    $crate::tera_response!(enable_minify $cm, $crate::EtagIfNoneMatch::default(), $name, data)
+                }),
+                // the context cannot be serialized, so building the response reports the error without caching it
+                ::std::result::Result::Err(_) => $crate::tera_response!(enable_minify $cm, $etag_if_none_match, $name, data),
+            }
+        }
+    };
+    ( auto disable_minify $cm:expr, $etag_if_none_match:expr, $name:expr, $data:expr ) => {
+        {
+            let data = &$data;
+
+            match $crate::auto_cache_key($name, data, false) {
+                ::std::result::Result::Ok(key) => $crate::tera_response_cache!($cm, $etag_if_none_match, key, {
+                    $crate::tera_response!(disable_minify $cm, $crate::EtagIfNoneMatch::default(), $name, data)
+                }),
+                // the context cannot be serialized, so building the response reports the error without caching it
+                ::std::result::Result::Err(_) => $crate::tera_response!(disable_minify $cm, $etag_if_none_match, $name, data),
+            }
+        }
+    };
+    ( cache_control $cache_control:expr => $cm:expr, $etag_if_none_match:expr, $key:expr, $gen:block ) => {
+        $crate::tera_response_cache!($cm, $etag_if_none_match, $key, $gen)
+            .cache_control(::std::option::Option::Some($cache_control))
+    };
     ($cm:expr, $etag_if_none_match:expr, $key:expr, $gen:block) => {
         match $cm.build_from_cache(&$etag_if_none_match, &$key) {
             Some(res) => res,
             None => {
                 let res = $gen;
 
-                match res.into_html_and_etag() {
-                    Some((content, etag)) => {
-                        let res = $crate::TeraResponse::build_cache(content.clone(), &etag);
-                        $cm.insert($key, (content, ::std::sync::Arc::new(etag)));
-                        res
-                    },
-                    None => $crate::TeraResponse::not_modified(),
-                }
+                $cm.insert_response($key, res)
             },
         }
     };
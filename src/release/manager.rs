@@ -1,32 +1,201 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{Arc, PoisonError, RwLock},
+    time::SystemTime,
+};
 
-use lru_time_cache::LruCache;
 use serde::Serialize;
-use tera::{Context, Tera};
+use tera::{Context, Error as TeraError, Tera};
 
-use super::TeraResponse;
-use crate::{functions::compute_data_etag, EntityTag, EtagIfNoneMatch};
+use super::{
+    cache::{CacheEntry, ResponseCache},
+    tera_response::build_time,
+};
+use crate::{
+    flash::PageSource,
+    fragment::{BlockTemplates, FragmentRequest},
+    functions::{compute_context_etag, compute_data_etag, spawn_blocking},
+    i18n::{expand_locales, resolve_localized_name, LocaleScope},
+    negotiation::build_from_json,
+    placeholder::Placeholders,
+    stream::{render_stream, StreamSource},
+    tera_response::TeraResponseInner,
+    CacheControl, CacheStats, EntityTag, EtagIfNoneMatch, HeadRequest, ResponseFormat,
+    TeraResponse, TeraTemplate,
+};
 
-#[allow(clippy::type_complexity)]
-/// To monitor the state of Tera.
+/// To monitor the state of Tera. The type parameter is a marker which distinguishes template sets managed by the same Rocket instance.
 #[derive(Educe)]
 #[educe(Debug)]
-pub struct TeraContextManager {
-    pub tera:    Tera,
+pub struct TeraContextManager<T = ()> {
+    pub tera:                   Arc<Tera>,
+    blocks:                     BlockTemplates,
+    /// The template set with the templates of `TeraTemplate` types which are registered the first time they are responded. It is the same as `tera` until then, and replaced by a copy for each registration, so that renders never wait for it.
     #[educe(Debug(ignore))]
-    cache_table: Mutex<LruCache<String, (Arc<str>, Arc<EntityTag<'static>>)>>,
+    template_tera:              RwLock<Arc<Tera>>,
+    overlays:                   HashMap<String, Arc<Tera>>,
+    pub(crate) fragment_header: Cow<'static, str>,
+    render_version:             String,
+    cache_control:              Option<CacheControl>,
+    placeholders:               Placeholders,
+    #[educe(Debug(ignore))]
+    cache_table:                Arc<ResponseCache>,
+    #[educe(Debug(ignore))]
+    marker:                     PhantomData<fn() -> T>,
 }
 
-impl TeraContextManager {
+impl<T> TeraContextManager<T> {
     #[inline]
-    pub(crate) fn new(tera: Tera, cache_capacity: usize) -> TeraContextManager {
+    pub(crate) fn new(
+        tera: Tera,
+        overlays: HashMap<String, Tera>,
+        fragment_header: Cow<'static, str>,
+        cache_table: ResponseCache,
+        render_version: String,
+        cache_control: Option<CacheControl>,
+        placeholders: Placeholders,
+    ) -> TeraContextManager<T> {
+        let blocks = BlockTemplates::new(&tera);
+
+        let tera = Arc::new(tera);
+
         TeraContextManager {
+            template_tera: RwLock::new(tera.clone()),
             tera,
-            cache_table: Mutex::new(LruCache::with_capacity(cache_capacity)),
+            blocks,
+            overlays: overlays
+                .into_iter()
+                .map(|(overlay_name, overlay)| (overlay_name, Arc::new(overlay)))
+                .collect(),
+            fragment_header,
+            render_version,
+            cache_control,
+            placeholders,
+            cache_table: Arc::new(cache_table),
+            marker: PhantomData,
         }
     }
 
-    /// Build a `TeraResponse`.
+    /// Get the template set of an overlay. An unknown overlay is an error like an unknown template, so it panics.
+    #[inline]
+    fn overlay_tera(&self, overlay: &str) -> &Arc<Tera> {
+        self.overlays.get(overlay).unwrap_or_else(|| {
+            panic!("The overlay `{}` is not added to the fairing of TeraResponse.", overlay)
+        })
+    }
+
+    /// Apply the default policy of the `Cache-Control` header and the placeholders set on the fairing.
+    #[inline]
+    fn with_defaults(&self, response: TeraResponse) -> TeraResponse {
+        response.cache_control(self.cache_control.clone()).placeholders(self.placeholders.clone())
+    }
+
+    /// Apply the defaults, and the policy of the `Cache-Control` header which overrides the default one for an entry of the cache.
+    #[inline]
+    fn with_entry_defaults(
+        &self,
+        response: TeraResponse,
+        cache_control: Option<Option<CacheControl>>,
+    ) -> TeraResponse {
+        let response = self.with_defaults(response);
+
+        match cache_control {
+            Some(cache_control) => response.cache_control(cache_control),
+            None => response,
+        }
+    }
+
+    /// Convert a context, and provide the placeholders of the values which differ for each response, such as `csp_nonce`.
+    #[inline]
+    fn context<V: Serialize>(&self, context: V) -> Context {
+        let mut context = Context::from_serialize(context).unwrap();
+
+        self.placeholders.insert_into(&mut context);
+
+        context
+    }
+
+    /// Get the source of a page rendered with the base template set.
+    #[inline]
+    pub(crate) fn page_source(&self, name: &str, context: Context, minify: bool) -> PageSource {
+        let tera = self.tera.clone();
+        let name: Arc<str> = Arc::from(name);
+
+        PageSource::new(
+            name.clone(),
+            context,
+            minify,
+            Arc::new(move |context| tera.render(&name, context)),
+        )
+    }
+
+    /// Get the source of a page rendered with a template localized for the first possible locale of the given locales sorted by preference.
+    #[inline]
+    fn localized_source<L: AsRef<str>>(
+        &self,
+        locales: &[L],
+        name: &str,
+        context: Context,
+        minify: bool,
+    ) -> PageSource {
+        let locales = expand_locales(locales);
+
+        let name: Arc<str> = Arc::from(resolve_localized_name(&locales, name, |name| {
+            self.tera.get_template(name).is_ok()
+        }));
+
+        let tera = self.tera.clone();
+
+        PageSource::new(
+            name.clone(),
+            context,
+            minify,
+            Arc::new(move |context| {
+                let _scope = LocaleScope::enter(locales.clone());
+
+                tera.render(&name, context)
+            }),
+        )
+    }
+
+    /// Get the source of a page which is only a block of a template.
+    #[inline]
+    fn block_source(&self, name: &str, block: &str, context: Context, minify: bool) -> PageSource {
+        let blocks = self.blocks.clone();
+        let name: Arc<str> = Arc::from(name);
+        let block = block.to_string();
+
+        PageSource::new(
+            name.clone(),
+            context,
+            minify,
+            Arc::new(move |context| blocks.render(&name, &block, &name, context)),
+        )
+    }
+
+    /// Get the source of a page rendered with the template set of an overlay.
+    #[inline]
+    fn overlay_source(
+        &self,
+        overlay: &str,
+        name: &str,
+        context: Context,
+        minify: bool,
+    ) -> PageSource {
+        let tera = self.overlay_tera(overlay).clone();
+        let name: Arc<str> = Arc::from(name);
+
+        PageSource::new(
+            name.clone(),
+            context,
+            minify,
+            Arc::new(move |context| tera.render(&name, context)),
+        )
+    }
+
+    /// Build a `TeraResponse`. The template is rendered when the response is sent, with the flash message of the request as `flash` if there is one. If rendering fails, the error is logged and **500 Internal Server Error** is responded.
     #[inline]
     pub fn build<S: AsRef<str>, V: Serialize>(
         &self,
@@ -35,20 +204,257 @@ impl TeraContextManager {
         name: S,
         context: V,
     ) -> TeraResponse {
-        self.tera
-            .render(name.as_ref(), &Context::from_serialize(context).unwrap())
-            .map(|html| {
-                let etag = compute_data_etag(html.as_bytes());
+        self.with_defaults(TeraResponse::build_page(
+            etag_if_none_match,
+            None,
+            self.page_source(name.as_ref(), self.context(context), minify),
+            false,
+        ))
+    }
 
-                if etag_if_none_match.weak_eq(&etag) {
-                    TeraResponse::not_modified()
-                } else {
-                    let html = if minify { html_minifier::minify(html).unwrap() } else { html };
+    /// Build a `TeraResponse` with the template of a `TeraTemplate` type as `build` does. The template is registered the first time, unless it is already registered in the fairing.
+    pub(crate) fn build_template<V: TeraTemplate>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        context: &V,
+    ) -> Result<TeraResponse, TeraError> {
+        let tera = self.template_tera::<V>()?;
 
-                    TeraResponse::build_not_cache(html, &etag)
-                }
-            })
-            .unwrap()
+        let source = PageSource::new(
+            Arc::from(V::NAME),
+            self.context(context),
+            V::MINIFY,
+            Arc::new(move |context| tera.render(V::NAME, context)),
+        );
+
+        Ok(self.with_defaults(TeraResponse::build_page(etag_if_none_match, None, source, false)))
+    }
+
+    /// Get a template set which has the template of a `TeraTemplate` type, and register it if needed.
+    fn template_tera<V: TeraTemplate>(&self) -> Result<Arc<Tera>, TeraError> {
+        if self.tera.get_template(V::NAME).is_ok() {
+            return Ok(self.tera.clone());
+        }
+
+        let template_tera =
+            self.template_tera.read().unwrap_or_else(PoisonError::into_inner).clone();
+
+        if template_tera.get_template(V::NAME).is_ok() {
+            return Ok(template_tera);
+        }
+
+        let mut template_tera = self.template_tera.write().unwrap_or_else(PoisonError::into_inner);
+
+        // another request may have registered it while waiting for the lock
+        if template_tera.get_template(V::NAME).is_err() {
+            let mut tera = Tera::clone(&template_tera);
+
+            V::register(&mut tera);
+
+            tera.get_template(V::NAME)?;
+
+            *template_tera = Arc::new(tera);
+        }
+
+        Ok(template_tera.clone())
+    }
+
+    /// Build a `TeraResponse` by rendering the template right away without a flash message, or get the error of rendering it, e.g. for error pages.
+    #[inline]
+    pub(crate) fn try_build<S: AsRef<str>, V: Serialize>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        context: V,
+    ) -> Result<TeraResponse, TeraError> {
+        let name = name.as_ref();
+        let context = self.context(context);
+
+        let html = self.tera.render(name, &context)?;
+
+        Ok(self.with_defaults(build_from_html(etag_if_none_match, minify, html)))
+    }
+
+    /// Build a `TeraResponse` with the template localized for the first possible locale of the given locales sorted by preference. For example, `index.de` is used instead of `index` if it is registered. Locale tags are normalized, so localized templates are named with canonical tags like `index.de-CH`. The `t` function of templates also uses these locales. The response varies on `Accept-Language`.
+    #[inline]
+    pub fn build_localized<L: AsRef<str>, S: AsRef<str>, V: Serialize>(
+        &self,
+        locales: &[L],
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        context: V,
+    ) -> TeraResponse {
+        self.with_defaults(TeraResponse::build_page(
+            etag_if_none_match,
+            None,
+            self.localized_source(locales, name.as_ref(), self.context(context), minify),
+            false,
+        ))
+        .vary("Accept-Language")
+    }
+
+    /// Build a `TeraResponse` whose ETag is computed from the version of the templates, the name of the template, the minify flag and the context, instead of the rendered HTML. If the ETag matches, the template is neither rendered nor minified. The version is computed from the names of the templates, the URLs of the assets, `cache_version` and the modification time of the executable binary file, so that it changes with embedded templates, functions, filters and globals as well. Templates loaded from files at runtime are not covered, so call `cache_version` on the fairing for them.
+    #[inline]
+    pub fn build_with_context_etag<S: AsRef<str>, V: Serialize>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        context: V,
+    ) -> TeraResponse {
+        let name = name.as_ref();
+
+        let etag = compute_context_etag(&self.render_version, name, minify, &context);
+
+        self.with_defaults(TeraResponse::build_page(
+            etag_if_none_match,
+            Some(etag),
+            self.page_source(name, self.context(context), minify),
+            false,
+        ))
+    }
+
+    /// Build a `TeraResponse` with only a block of a template, e.g. for htmx or Turbo to update a part of a page. The block is cut out of the whole page, so `super()` still works and the context must be the one of the page. Its ETag is computed from the rendered block, so it never matches the ETag of the whole page.
+    #[inline]
+    pub fn build_block<S: AsRef<str>, B: AsRef<str>, V: Serialize>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        block: B,
+        context: V,
+    ) -> TeraResponse {
+        self.with_defaults(TeraResponse::build_page(
+            etag_if_none_match,
+            None,
+            self.block_source(name.as_ref(), block.as_ref(), self.context(context), minify),
+            false,
+        ))
+    }
+
+    /// Build a `TeraResponse` with only a block of a template if a fragment is requested, or with the whole template otherwise. The request header of fragments is sent in the `Vary` header.
+    #[inline]
+    pub fn build_fragment<S: AsRef<str>, B: AsRef<str>, V: Serialize>(
+        &self,
+        fragment_request: &FragmentRequest<T>,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        block: B,
+        context: V,
+    ) -> TeraResponse {
+        let response = if fragment_request.is_requested() {
+            self.build_block(etag_if_none_match, minify, name, block, context)
+        } else {
+            self.build(etag_if_none_match, minify, name, context)
+        };
+
+        // caches must not serve a fragment for a request of the whole page, or the reverse
+        response.vary(self.fragment_header.clone())
+    }
+
+    /// Build a `TeraResponse` with only the headers for a `HEAD` request, or the same as `build` for other requests. The template is still rendered to compute its ETag, but it is not minified, and `Content-Length` is not sent because the length of the minified HTML is unknown. A response of the cache of `tera_response_cache!` does not need this, because its headers are computed from the cache.
+    #[inline]
+    pub fn build_head<S: AsRef<str>, V: Serialize>(
+        &self,
+        head_request: &HeadRequest,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        context: V,
+    ) -> TeraResponse {
+        if head_request.is_head() {
+            self.with_defaults(build_head_from_html(
+                etag_if_none_match,
+                self.render_with_placeholders(name, context),
+            ))
+        } else {
+            self.build(etag_if_none_match, minify, name, context)
+        }
+    }
+
+    /// Build a `TeraResponse` in the format preferred by the client. HTML is rendered from the template as `build` does, and JSON is serialized from the context, with the ETag computed from the JSON. `Accept` is sent in the `Vary` header. JSON is never stored in the cache of `tera_response_cache!`, but HTML is, so do not use the same key for a route which responds both formats.
+    #[inline]
+    pub fn build_negotiated<S: AsRef<str>, V: Serialize>(
+        &self,
+        format: ResponseFormat,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        context: V,
+    ) -> TeraResponse {
+        let response = match format {
+            ResponseFormat::Html => self.build(etag_if_none_match, minify, name, context),
+            ResponseFormat::Json => {
+                self.with_defaults(build_from_json(etag_if_none_match, context))
+            },
+        };
+
+        // caches must not serve JSON to browsers, or the reverse
+        response.vary("Accept")
+    }
+
+    /// Build a `TeraResponse` with the template set of an overlay, such as a theme or a tenant. Templates of the overlay override the templates with the same names in the base set, so `extends` and `include` are resolved against the overlay first. Like a template which is not registered, an overlay which is not added to the fairing makes it panic.
+    #[inline]
+    pub fn build_overlay<O: AsRef<str>, S: AsRef<str>, V: Serialize>(
+        &self,
+        overlay: O,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        context: V,
+    ) -> TeraResponse {
+        self.with_defaults(TeraResponse::build_page(
+            etag_if_none_match,
+            None,
+            self.overlay_source(overlay.as_ref(), name.as_ref(), self.context(context), minify),
+            false,
+        ))
+    }
+
+    /// Build a `TeraResponse` for async handlers, whose template is rendered as `build` does, but the runtime is told to move its other tasks to other threads first, so that rendering and minifying a large page do not stall them.
+    pub async fn build_async<S: Into<String>, V: Serialize>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        minify: bool,
+        name: S,
+        context: V,
+    ) -> TeraResponse {
+        self.with_defaults(TeraResponse::build_page(
+            etag_if_none_match,
+            None,
+            self.page_source(&name.into(), self.context(context), minify),
+            true,
+        ))
+    }
+
+    /// Build a `TeraResponse` which streams the rendered HTML with chunked transfer instead of holding it in memory as a whole, e.g. for a huge page. The template is rendered on the blocking thread pool while the body is being sent. The HTML is not minified and no ETag is sent, because both need the whole HTML. Like `build`, it panics if the template is not registered. If rendering fails in the middle, the error is logged and the response is aborted, so that the client never takes a truncated page as complete.
+    pub fn build_stream<S: Into<String>, V: Serialize>(&self, name: S, context: V) -> TeraResponse {
+        self.with_defaults(TeraResponse::build_stream(
+            &EtagIfNoneMatch::default(),
+            None,
+            self.stream_source(name.into(), context),
+        ))
+    }
+
+    /// Build a `TeraResponse` which streams the rendered HTML as `build_stream` does, with an ETag computed from the version of the templates, the name of the template and the context as `build_with_context_etag` does. If the ETag matches, the template is not rendered.
+    pub fn build_stream_with_context_etag<S: Into<String>, V: Serialize>(
+        &self,
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        name: S,
+        context: V,
+    ) -> TeraResponse {
+        let name = name.into();
+
+        let etag = compute_context_etag(&self.render_version, &name, false, &context);
+
+        self.with_defaults(TeraResponse::build_stream(
+            etag_if_none_match,
+            Some(etag),
+            self.stream_source(name, context),
+        ))
     }
 
     /// Build a `TeraResponse`.
@@ -58,41 +464,226 @@ impl TeraContextManager {
         etag_if_none_match: &EtagIfNoneMatch<'_>,
         key: K,
     ) -> Option<TeraResponse> {
-        self.cache_table.lock().unwrap().get(key.as_ref()).map(|(html, etag)| {
-            if etag_if_none_match.weak_eq(etag) {
-                TeraResponse::not_modified()
-            } else {
-                TeraResponse::build_cache(html.clone(), etag)
-            }
+        self.cache_table.get(key.as_ref()).map(|entry| {
+            let (html, etag) = entry.value;
+
+            self.with_entry_defaults(
+                if etag_if_none_match.weak_eq(&etag) {
+                    TeraResponse::not_modified()
+                } else {
+                    TeraResponse::build_cache(html, &etag)
+                },
+                entry.cache_control,
+            )
+            .source(entry.source)
         })
     }
 
-    /// Render a template.
+    /// Insert the HTML and the ETag of a `TeraResponse` into the cache, and respond them. A page which is rendered when the response is sent is inserted then, unless it is rendered with a flash message. A policy of the `Cache-Control` header set on the `TeraResponse` is kept for the responses of the cache, instead of the default policy.
+    #[doc(hidden)]
+    #[inline]
+    pub fn insert_response<S: Into<String>>(&self, key: S, response: TeraResponse) -> TeraResponse {
+        // the HTML of a response built by `build_head` or `build_stream` is unknown, and JSON is not HTML
+        if !response.is_cacheable() {
+            return response;
+        }
+
+        let cache_control = response.get_cache_control().cloned();
+
+        let cache_control = (cache_control != self.cache_control).then_some(cache_control);
+
+        // the context is kept, so that responses of the cache can be rendered again with flash messages
+        let source = response.source.clone();
+
+        let response = match response.inner {
+            Some(
+                inner @ TeraResponseInner::Page {
+                    ..
+                },
+            ) => {
+                let mut response =
+                    TeraResponse::with_inner(Some(inner)).last_modified(Some(build_time()));
+
+                let cache_table = self.cache_table.clone();
+                let key = key.into();
+                let entry_cache_control = cache_control.clone();
+                let entry_source = source.clone();
+
+                response.cache_insert = Some(Box::new(move |html, etag| {
+                    cache_table.insert(key, CacheEntry {
+                        value:         (Arc::from(html), Arc::new(etag.clone())),
+                        cache_control: entry_cache_control,
+                        source:        entry_source,
+                    });
+                }));
+
+                response
+            },
+            _ => match response.into_html_and_etag() {
+                Some((html, etag)) => {
+                    let response = TeraResponse::build_cache(html.clone(), &etag);
+
+                    self.cache_table.insert(key.into(), CacheEntry {
+                        value:         (html, Arc::new(etag)),
+                        cache_control: cache_control.clone(),
+                        source:        source.clone(),
+                    });
+
+                    response
+                },
+                None => TeraResponse::not_modified(),
+            },
+        };
+
+        self.with_entry_defaults(response, cache_control).source(source)
+    }
+
+    /// Render a template with the placeholders of the values which differ for each response.
+    #[inline]
+    fn render_with_placeholders<S: AsRef<str>, V: Serialize>(&self, name: S, context: V) -> String {
+        self.tera.render(name.as_ref(), &self.context(context)).unwrap()
+    }
+
+    /// Render only a block of a template with the placeholders of the values which differ for each response.
+    #[inline]
+    fn render_block_with_placeholders<S: AsRef<str>, B: AsRef<str>, V: Serialize>(
+        &self,
+        name: S,
+        block: B,
+        context: V,
+    ) -> String {
+        self.block_source(name.as_ref(), block.as_ref(), self.context(context), false)
+            .render_html(None)
+            .unwrap()
+    }
+
+    /// Render a template with the template set of an overlay and the placeholders of the values which differ for each response.
+    #[inline]
+    fn render_overlay_with_placeholders<O: AsRef<str>, S: AsRef<str>, V: Serialize>(
+        &self,
+        overlay: O,
+        name: S,
+        context: V,
+    ) -> String {
+        self.overlay_source(overlay.as_ref(), name.as_ref(), self.context(context), false)
+            .render_html(None)
+            .unwrap()
+    }
+
+    /// Render a template. Values which differ for each response are new values which are not sent in any header, so `csp_nonce` is not protected by the `Content-Security-Policy` header of the fairing, and `csrf_token` is empty.
     #[inline]
     pub fn render<S: AsRef<str>, V: Serialize>(&self, name: S, context: V) -> String {
-        self.tera.render(name.as_ref(), &Context::from_serialize(context).unwrap()).unwrap()
+        self.placeholders.detach(self.render_with_placeholders(name, context))
+    }
+
+    /// Render only a block of a template. Values which differ for each response are handled as `render` does.
+    #[inline]
+    pub fn render_block<S: AsRef<str>, B: AsRef<str>, V: Serialize>(
+        &self,
+        name: S,
+        block: B,
+        context: V,
+    ) -> String {
+        self.placeholders.detach(self.render_block_with_placeholders(name, block, context))
+    }
+
+    /// Render a template with the template set of an overlay. Values which differ for each response are handled as `render` does.
+    #[inline]
+    pub fn render_overlay<O: AsRef<str>, S: AsRef<str>, V: Serialize>(
+        &self,
+        overlay: O,
+        name: S,
+        context: V,
+    ) -> String {
+        self.placeholders.detach(self.render_overlay_with_placeholders(overlay, name, context))
+    }
+
+    /// Render a template localized for the first possible locale of the given locales sorted by preference. Values which differ for each response are handled as `render` does.
+    #[inline]
+    pub fn render_localized<L: AsRef<str>, S: AsRef<str>, V: Serialize>(
+        &self,
+        locales: &[L],
+        name: S,
+        context: V,
+    ) -> String {
+        self.placeholders.detach(self.render_localized_with_placeholders(locales, name, context))
+    }
+
+    /// Render a template on the blocking thread pool. Values which differ for each response are handled as `render` does.
+    pub async fn render_async<S: Into<String>, V: Serialize>(&self, name: S, context: V) -> String {
+        let tera = self.tera.clone();
+        let name = name.into();
+        let context = self.context(context);
+
+        let html = spawn_blocking(move || tera.render(&name, &context).unwrap()).await;
+
+        self.placeholders.detach(html)
+    }
+
+    /// Get the source of a streamed page, which is rendered on the blocking thread pool into the body of the response. The template is checked first, so that an unknown template is not responded as **200 OK**.
+    fn stream_source<V: Serialize>(&self, name: String, context: V) -> StreamSource {
+        self.tera.get_template(&name).unwrap();
+
+        let tera = self.tera.clone();
+
+        StreamSource::new(self.context(context), move |context| {
+            render_stream(name.clone(), move |writer| tera.render_to(&name, &context, writer))
+        })
+    }
+
+    /// Render a localized template with the placeholders of the values which differ for each response.
+    #[inline]
+    fn render_localized_with_placeholders<L: AsRef<str>, S: AsRef<str>, V: Serialize>(
+        &self,
+        locales: &[L],
+        name: S,
+        context: V,
+    ) -> String {
+        self.localized_source(locales, name.as_ref(), self.context(context), false)
+            .render_html(None)
+            .unwrap()
+    }
+
+    /// Get the `Last-Modified` timestamp of the responses of a template, e.g. to check `IfModifiedSince` before rendering. Templates are embedded in the **release** profile, so it is the time when the executable binary file was built. Only responses of the cache of `tera_response_cache!` use it as their `Last-Modified` timestamp by default, because other responses may have contexts which change more often.
+    #[inline]
+    pub fn last_modified<S: AsRef<str>>(&self, _name: S) -> Option<SystemTime> {
+        Some(build_time())
     }
 
     /// Clear cache.
     #[inline]
     pub fn clear_cache(&self) {
-        self.cache_table.lock().unwrap().clear();
+        self.cache_table.clear();
     }
 
     /// Check if a cache key exists.
     #[inline]
     pub fn contains_key<S: AsRef<str>>(&self, key: S) -> bool {
-        self.cache_table.lock().unwrap().get(key.as_ref()).is_some()
+        self.cache_table.contains_key(key.as_ref())
+    }
+
+    /// Get the statistics of the cache.
+    #[inline]
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache_table.stats()
+    }
+
+    /// Get the keys of the cache, e.g. for debugging.
+    #[inline]
+    pub fn cache_keys(&self) -> Vec<String> {
+        self.cache_table.keys()
     }
 
     /// Get the cache by a specific key.
     #[inline]
     pub fn get<S: AsRef<str>>(&self, key: S) -> Option<(Arc<str>, Arc<EntityTag<'static>>)> {
-        self.cache_table
-            .lock()
-            .unwrap()
-            .get(key.as_ref())
-            .map(|(html, etag)| (html.clone(), etag.clone()))
+        self.cache_table.get(key.as_ref()).map(|entry| entry.value)
+    }
+
+    /// Insert an entry into the cache.
+    #[inline]
+    pub(crate) fn insert_entry<S: Into<String>>(&self, key: S, entry: CacheEntry) {
+        self.cache_table.insert(key.into(), entry);
     }
 
     /// Insert a cache.
@@ -102,6 +693,34 @@ impl TeraContextManager {
         key: S,
         cache: (Arc<str>, Arc<EntityTag<'static>>),
     ) -> Option<(Arc<str>, Arc<EntityTag<'static>>)> {
-        self.cache_table.lock().unwrap().insert(key.into(), cache)
+        self.cache_table.insert(key.into(), cache.into())
+    }
+}
+
+#[inline]
+fn build_from_html(
+    etag_if_none_match: &EtagIfNoneMatch<'_>,
+    minify: bool,
+    html: String,
+) -> TeraResponse {
+    let etag = compute_data_etag(html.as_bytes());
+
+    if etag_if_none_match.weak_eq(&etag) {
+        TeraResponse::not_modified()
+    } else {
+        let html = if minify { html_minifier::minify(html).unwrap() } else { html };
+
+        TeraResponse::build_not_cache(html, &etag)
+    }
+}
+
+#[inline]
+fn build_head_from_html(etag_if_none_match: &EtagIfNoneMatch<'_>, html: String) -> TeraResponse {
+    let etag = compute_data_etag(html.as_bytes());
+
+    if etag_if_none_match.weak_eq(&etag) {
+        TeraResponse::not_modified()
+    } else {
+        TeraResponse::build_head(&etag)
     }
 }
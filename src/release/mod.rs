@@ -1,3 +1,9 @@
+mod assets;
+
+mod cache;
+
+mod disk_cache;
+
 mod tera_response;
 
 mod manager;
@@ -6,5 +12,10 @@ mod fairing;
 
 mod macros;
 
+pub use assets::*;
+#[doc(hidden)]
+pub use fairing::*;
 pub use manager::*;
-pub use tera_response::*;
+
+/// The type which templates are registered into in the fairing of `TeraResponse`.
+pub type TeraRegistry = tera::Tera;
@@ -1,57 +1,67 @@
-use std::{io::Cursor, sync::Arc};
-
-use rc_u8_reader::ArcU8Reader;
-use rocket::{
-    http::Status,
-    request::Request,
-    response::{self, Responder, Response},
+use std::{
+    env,
+    sync::{Arc, OnceLock},
+    time::SystemTime,
 };
 
-use crate::EntityTag;
+use crate::{
+    flash::PageSource,
+    tera_response::{TeraResponseInner, HTML_CONTENT_TYPE},
+    CacheControl, EntityTag, TeraResponse,
+};
 
-#[derive(Debug)]
-enum TeraResponseInner {
-    NotCache { content: String, etag: String },
-    Cache { content: Arc<str>, etag: String },
-}
+/// The time when the executable binary file was built, or when this function is called for the first time if it is unknown.
+pub(crate) fn build_time() -> SystemTime {
+    static BUILD_TIME: OnceLock<SystemTime> = OnceLock::new();
 
-#[derive(Debug)]
-/// To respond HTML.
-pub struct TeraResponse {
-    inner: Option<TeraResponseInner>,
+    *BUILD_TIME.get_or_init(|| {
+        env::current_exe()
+            .and_then(|path| path.metadata())
+            .and_then(|metadata| metadata.modified())
+            .unwrap_or_else(|_| SystemTime::now())
+    })
 }
 
 impl TeraResponse {
+    /// Get the policy of the `Cache-Control` header of the response.
+    #[inline]
+    pub(crate) fn get_cache_control(&self) -> Option<&CacheControl> {
+        self.cache_control.as_ref()
+    }
+
+    /// Whether the whole HTML of the response is known and not specific to a request, so that it can be cached. The cache only holds HTML, so JSON is never cached.
     #[inline]
-    pub(crate) fn build_not_cache<S: Into<String>>(
-        content: S,
-        etag: &EntityTag<'static>,
-    ) -> TeraResponse {
-        TeraResponse {
-            inner: Some(TeraResponseInner::NotCache {
-                content: content.into(),
-                etag:    etag.to_string(),
-            }),
+    pub(crate) fn is_cacheable(&self) -> bool {
+        match self.inner {
+            Some(TeraResponseInner::NotCache {
+                content_type, ..
+            }) => content_type == HTML_CONTENT_TYPE,
+            Some(TeraResponseInner::Cache {
+                ..
+            })
+            | Some(TeraResponseInner::Page {
+                ..
+            }) => true,
+            _ => false,
         }
     }
 
     #[doc(hidden)]
     #[inline]
     pub fn build_cache(content: Arc<str>, etag: &EntityTag<'static>) -> TeraResponse {
-        TeraResponse {
-            inner: Some(TeraResponseInner::Cache {
-                content,
-                etag: etag.to_string(),
-            }),
-        }
+        TeraResponse::with_inner(Some(TeraResponseInner::Cache {
+            content,
+            etag: etag.to_string(),
+        }))
+        .last_modified(Some(build_time()))
     }
 
-    #[doc(hidden)]
+    /// Set how the page is rendered again when the request carries a flash message.
     #[inline]
-    pub const fn not_modified() -> TeraResponse {
-        TeraResponse {
-            inner: None
-        }
+    pub(crate) fn source(mut self, source: Option<PageSource>) -> TeraResponse {
+        self.source = source;
+
+        self
     }
 
     #[doc(hidden)]
@@ -61,6 +71,7 @@ impl TeraResponse {
             Some(TeraResponseInner::NotCache {
                 content,
                 mut etag,
+                ..
             }) => {
                 etag.remove(etag.len() - 1);
                 etag.remove(0);
@@ -80,39 +91,19 @@ impl TeraResponse {
 
                 Some((content, etag))
             },
-            None => None,
+            Some(TeraResponseInner::Head {
+                ..
+            })
+            | Some(TeraResponseInner::Stream {
+                ..
+            })
+            | Some(TeraResponseInner::Page {
+                ..
+            })
+            | Some(TeraResponseInner::PageStream {
+                ..
+            })
+            | None => None,
         }
     }
 }
-
-impl<'r, 'o: 'r> Responder<'r, 'o> for TeraResponse {
-    #[inline]
-    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'o> {
-        let mut response = Response::build();
-
-        if let Some(inner) = self.inner {
-            response.raw_header("Content-Type", "text/html; charset=utf-8");
-
-            match inner {
-                TeraResponseInner::NotCache {
-                    content,
-                    etag,
-                } => {
-                    response.raw_header("Etag", etag);
-                    response.sized_body(content.len(), Cursor::new(content));
-                },
-                TeraResponseInner::Cache {
-                    content,
-                    etag,
-                } => {
-                    response.raw_header("Etag", etag);
-                    response.sized_body(content.len(), ArcU8Reader::new(content));
-                },
-            }
-        } else {
-            response.status(Status::NotModified);
-        }
-
-        response.ok()
-    }
-}
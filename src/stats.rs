@@ -0,0 +1,108 @@
+use std::{io::Cursor, marker::PhantomData};
+
+use rocket::{
+    data::Data,
+    http::{ContentType, Method, Status},
+    request::Request,
+    response::Response,
+    route::{Handler, Outcome, Route},
+};
+use serde::Serialize;
+
+use crate::TeraContextManager;
+
+/// Statistics of the cache of a `TeraContextManager`. The cache is generated only when you are using the **release** profile, so they are all zero otherwise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    /// The number of lookups which found an entry.
+    pub hits:      u64,
+    /// The number of lookups which found nothing.
+    pub misses:    u64,
+    /// The number of inserted entries.
+    pub inserts:   u64,
+    /// The number of entries evicted because the cache was full.
+    pub evictions: u64,
+    /// The number of entries currently in the cache.
+    pub entries:   usize,
+    /// The total length in bytes of the HTML currently in the cache.
+    pub bytes:     usize,
+}
+
+/// A route handler which responds the `CacheStats` of a `TeraContextManager` as JSON, e.g. for an admin dashboard. The type parameter is the marker of the `TeraContextManager`. Mount it to a path, which should be protected by yourself.
+///
+/// ```rust,ignore
+/// rocket::build().mount("/admin/tera-cache", TeraCacheStats::new())
+/// ```
+pub struct TeraCacheStats<T = ()> {
+    marker: PhantomData<fn() -> T>,
+}
+
+impl TeraCacheStats {
+    /// Create an instance of `TeraCacheStats` for the default `TeraContextManager`.
+    #[inline]
+    pub fn new() -> TeraCacheStats {
+        TeraCacheStats::default()
+    }
+}
+
+impl<T> TeraCacheStats<T> {
+    /// Respond the statistics of the `TeraContextManager` of another marker type.
+    #[inline]
+    pub fn tagged<U>(self) -> TeraCacheStats<U> {
+        TeraCacheStats {
+            marker: PhantomData
+        }
+    }
+}
+
+impl<T> Default for TeraCacheStats<T> {
+    #[inline]
+    fn default() -> Self {
+        TeraCacheStats {
+            marker: PhantomData
+        }
+    }
+}
+
+impl<T> Clone for TeraCacheStats<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        TeraCacheStats::default()
+    }
+}
+
+#[rocket::async_trait]
+impl<T: 'static> Handler for TeraCacheStats<T> {
+    async fn handle<'r>(&self, req: &'r Request<'_>, _data: Data<'r>) -> Outcome<'r> {
+        let cm = match req.rocket().state::<TeraContextManager<T>>() {
+            Some(cm) => cm,
+            None => {
+                rocket::error!(
+                    "TeraContextManager is not managed. Attach the fairing of TeraResponse."
+                );
+
+                return Outcome::error(Status::InternalServerError);
+            },
+        };
+
+        let json = serde_json::to_string(&cm.cache_stats()).unwrap();
+
+        let mut response = Response::build();
+
+        response.header(ContentType::JSON);
+        response.raw_header("Cache-Control", "no-store");
+        response.sized_body(json.len(), Cursor::new(json));
+
+        Outcome::Success(response.finalize())
+    }
+}
+
+impl<T: 'static> From<TeraCacheStats<T>> for Vec<Route> {
+    #[inline]
+    fn from(stats: TeraCacheStats<T>) -> Self {
+        let mut route = Route::new(Method::Get, "/", stats);
+        route.name = Some("TeraCacheStats".into());
+
+        vec![route]
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    io::{self, BufWriter, Cursor, Read, Write},
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
+};
+
+use rocket::tokio::{
+    io::{AsyncRead, ReadBuf},
+    sync::mpsc::{channel, Receiver, Sender},
+    task,
+};
+use tera::{Context, Error as TeraError};
+
+use crate::flash::FlashValue;
+
+/// The size of the chunks sent from the renderer to the body of a streaming response.
+const STREAM_CHUNK_SIZE: usize = 8 * 1024;
+
+/// The number of chunks which can be queued between the renderer and the body of a streaming response, which bounds the memory used by a response.
+const STREAM_CHANNEL_CAPACITY: usize = 2;
+
+/// The body of a streaming response. It reads the chunks rendered on the blocking thread pool, and fails if the rendering fails, so that the response is aborted instead of ending as if it were complete.
+#[derive(Debug)]
+pub(crate) struct StreamBody {
+    receiver: Receiver<io::Result<Vec<u8>>>,
+    chunk:    Cursor<Vec<u8>>,
+}
+
+impl AsyncRead for StreamBody {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        loop {
+            let n = this.chunk.read(buf.initialize_unfilled())?;
+
+            if n > 0 {
+                buf.advance(n);
+
+                return Poll::Ready(Ok(()));
+            }
+
+            match this.receiver.poll_recv(cx) {
+                Poll::Ready(Some(Ok(chunk))) => this.chunk = Cursor::new(chunk),
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Err(err)),
+                // the renderer has finished
+                Poll::Ready(None) => return Poll::Ready(Ok(())),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Start rendering a page into the body of a streaming response from a context.
+type StreamStarter = Box<dyn FnOnce(Context) -> StreamBody + Send>;
+
+/// The context of a streamed page and how it is rendered, so that rendering starts when its response is sent, with the flash message of the request if there is one.
+#[derive(Educe)]
+#[educe(Debug)]
+pub(crate) struct StreamSource {
+    context: Context,
+    #[educe(Debug(ignore))]
+    starter: StreamStarter,
+}
+
+impl StreamSource {
+    #[inline]
+    pub(crate) fn new<F: FnOnce(Context) -> StreamBody + Send + 'static>(
+        context: Context,
+        starter: F,
+    ) -> StreamSource {
+        StreamSource {
+            context,
+            starter: Box::new(starter),
+        }
+    }
+
+    /// Start rendering, with the flash message as `flash` if there is one.
+    #[inline]
+    pub(crate) fn start(self, flash: Option<&FlashValue>) -> StreamBody {
+        let mut context = self.context;
+
+        if let Some(flash) = flash {
+            flash.insert_into(&mut context);
+        }
+
+        (self.starter)(context)
+    }
+}
+
+/// Sends rendered HTML to the body from a thread of the blocking thread pool.
+struct ChannelWriter {
+    sender: Sender<io::Result<Vec<u8>>>,
+    closed: bool,
+}
+
+impl Write for ChannelWriter {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.sender.blocking_send(Ok(buf.to_vec())) {
+            Ok(()) => Ok(buf.len()),
+            Err(_) => {
+                // the body has been dropped, e.g. the client is gone
+                self.closed = true;
+
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "the body has been dropped"))
+            },
+        }
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Render a template on the blocking thread pool into the body of a streaming response. The renderer waits while the queued chunks are not sent yet, so the HTML is never held in memory as a whole. If the rendering fails, the error is logged and the body fails, so that the client sees an aborted response instead of a truncated page.
+pub(crate) fn render_stream<F>(name: String, render: F) -> StreamBody
+where
+    F: FnOnce(&mut dyn Write) -> Result<(), TeraError> + Send + 'static, {
+    let (sender, receiver) = channel(STREAM_CHANNEL_CAPACITY);
+
+    task::spawn_blocking(move || {
+        let mut writer = BufWriter::with_capacity(STREAM_CHUNK_SIZE, ChannelWriter {
+            sender,
+            closed: false,
+        });
+
+        let result = render(&mut writer)
+            .map_err(|err| err.to_string())
+            .and_then(|_| writer.flush().map_err(|err| err.to_string()));
+
+        if let Err(err) = result {
+            // the buffered HTML is discarded, so that nothing is sent after the error
+            let (writer, _) = writer.into_parts();
+
+            if !writer.closed {
+                rocket::error!("Failed to stream the template `{}`: {}", name, err);
+
+                let _ = writer.sender.blocking_send(Err(io::Error::new(io::ErrorKind::Other, err)));
+            }
+        }
+    });
+
+    StreamBody {
+        receiver,
+        chunk: Cursor::new(Vec::new()),
+    }
+}
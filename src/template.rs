@@ -0,0 +1,50 @@
+use rocket::{
+    http::Status,
+    request::Request,
+    response::{self, Responder},
+};
+use serde::Serialize;
+
+use crate::{functions::etag_if_none_match, TeraContextManager, TeraRegistry};
+
+/// A context which knows the name of its template, so it can be responded directly. It can be derived by `#[derive(TeraTemplate)]` with the `derive` feature.
+pub trait TeraTemplate: Serialize {
+    /// The name of the template.
+    const NAME: &'static str;
+
+    /// Whether to minify the rendered HTML.
+    const MINIFY: bool = true;
+
+    /// Register the template. It is called the first time the template is responded, unless the template is already registered, e.g. in the fairing of `TeraResponse`.
+    #[inline]
+    fn register(_tera: &mut TeraRegistry) {}
+
+    /// Render the template with this context as a response, by using the `TeraContextManager<T>` managed by Rocket. `#[derive(TeraTemplate)]` uses the marker type given by `tagged`, or `()` by default.
+    #[inline]
+    fn respond_template<'r, 'o: 'r, T: 'static>(
+        &self,
+        request: &'r Request<'_>,
+    ) -> response::Result<'o>
+    where
+        Self: Sized, {
+        let cm = match request.rocket().state::<TeraContextManager<T>>() {
+            Some(cm) => cm,
+            None => {
+                rocket::error!(
+                    "TeraContextManager is not managed. Attach the fairing of TeraResponse."
+                );
+
+                return Err(Status::InternalServerError);
+            },
+        };
+
+        match cm.build_template(&etag_if_none_match(request), self) {
+            Ok(response) => response.respond_to(request),
+            Err(err) => {
+                rocket::error!("Failed to register the template `{}`: {}", Self::NAME, err);
+
+                Err(Status::InternalServerError)
+            },
+        }
+    }
+}
@@ -0,0 +1,556 @@
+#[cfg(not(debug_assertions))]
+use std::sync::Arc;
+use std::{borrow::Cow, io::Cursor, time::SystemTime};
+
+#[cfg(not(debug_assertions))]
+use rc_u8_reader::ArcU8Reader;
+use rocket::{
+    http::Status,
+    request::Request,
+    response::{self, Responder, Response},
+};
+
+use crate::{
+    flash::{flash_cache_control, FlashValue, PageSource},
+    functions::{block_in_place, etag_if_none_match},
+    head::{is_head_request, UnknownSizeBody},
+    last_modified::{format_http_date, IfModifiedSince},
+    placeholder::{
+        client_cache_control, mix_client_values, substitute, Placeholders, SubstitutingBody,
+    },
+    stream::{StreamBody, StreamSource},
+    CacheControl, EntityTag, EtagIfNoneMatch,
+};
+
+pub(crate) const HTML_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// Store the HTML and the ETag of a page into a cache once it is rendered without a flash message.
+pub(crate) type CacheInsert = Box<dyn FnOnce(&str, &EntityTag<'static>) + Send>;
+
+#[derive(Debug)]
+pub(crate) enum TeraResponseInner {
+    NotCache {
+        content:      String,
+        etag:         String,
+        content_type: &'static str,
+    },
+    #[cfg(not(debug_assertions))]
+    Cache {
+        content: Arc<str>,
+        etag:    String,
+    },
+    Head {
+        etag: String,
+    },
+    Stream {
+        body: StreamBody,
+        etag: Option<String>,
+    },
+    /// HTML which is rendered from the source of the response when it is sent, with an ETag computed from the context if it is given, or from the HTML otherwise.
+    Page {
+        etag:               Option<EntityTag<'static>>,
+        etag_if_none_match: EtagIfNoneMatch<'static>,
+        /// Whether the runtime is told to leave the current thread while rendering.
+        blocking:           bool,
+    },
+    /// HTML which is rendered into a streaming body when the response is sent.
+    PageStream {
+        source:             StreamSource,
+        etag:               Option<EntityTag<'static>>,
+        etag_if_none_match: EtagIfNoneMatch<'static>,
+    },
+}
+
+impl TeraResponseInner {
+    /// Whether the content is JSON, which is not rendered from a template.
+    #[inline]
+    fn is_json(&self) -> bool {
+        matches!(self, TeraResponseInner::NotCache {
+            content_type: JSON_CONTENT_TYPE,
+            ..
+        })
+    }
+
+    #[inline]
+    fn etag_mut(&mut self) -> Option<&mut String> {
+        match self {
+            TeraResponseInner::NotCache {
+                etag, ..
+            }
+            | TeraResponseInner::Head {
+                etag,
+            } => Some(etag),
+            TeraResponseInner::Stream {
+                etag, ..
+            } => etag.as_mut(),
+            #[cfg(not(debug_assertions))]
+            TeraResponseInner::Cache {
+                etag, ..
+            } => Some(etag),
+            // pages are rendered before their ETags are needed
+            TeraResponseInner::Page {
+                ..
+            }
+            | TeraResponseInner::PageStream {
+                ..
+            } => None,
+        }
+    }
+}
+
+#[derive(Educe)]
+#[educe(Debug)]
+/// To respond HTML, or JSON serialized from the context of a template.
+pub struct TeraResponse {
+    pub(crate) inner:             Option<TeraResponseInner>,
+    pub(crate) last_modified:     Option<SystemTime>,
+    /// The modification time of the template, which is used as the `Last-Modified` timestamp if the response goes through `tera_response_cache!`, as a response of the cache does in the **release** profile.
+    #[cfg(debug_assertions)]
+    pub(crate) template_modified: Option<SystemTime>,
+    pub(crate) cache_control:     Option<CacheControl>,
+    pub(crate) placeholders:      Placeholders,
+    /// How the page is rendered when the response is sent, or rendered again when the request carries a flash message.
+    pub(crate) source:            Option<PageSource>,
+    #[educe(Debug(ignore))]
+    pub(crate) cache_insert:      Option<CacheInsert>,
+    pub(crate) vary:              Vec<Cow<'static, str>>,
+}
+
+impl TeraResponse {
+    #[inline]
+    pub(crate) const fn with_inner(inner: Option<TeraResponseInner>) -> TeraResponse {
+        TeraResponse {
+            inner,
+            last_modified: None,
+            #[cfg(debug_assertions)]
+            template_modified: None,
+            cache_control: None,
+            placeholders: Placeholders::new(),
+            source: None,
+            cache_insert: None,
+            vary: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn build_not_cache<S: Into<String>>(
+        content: S,
+        etag: &EntityTag<'static>,
+    ) -> TeraResponse {
+        TeraResponse::with_inner(Some(TeraResponseInner::NotCache {
+            content:      content.into(),
+            etag:         etag.to_string(),
+            content_type: HTML_CONTENT_TYPE,
+        }))
+    }
+
+    #[inline]
+    pub(crate) fn build_json_not_cache<S: Into<String>>(
+        content: S,
+        etag: &EntityTag<'static>,
+    ) -> TeraResponse {
+        TeraResponse::with_inner(Some(TeraResponseInner::NotCache {
+            content:      content.into(),
+            etag:         etag.to_string(),
+            content_type: JSON_CONTENT_TYPE,
+        }))
+    }
+
+    #[inline]
+    pub(crate) fn build_head(etag: &EntityTag<'static>) -> TeraResponse {
+        TeraResponse::with_inner(Some(TeraResponseInner::Head {
+            etag: etag.to_string()
+        }))
+    }
+
+    /// Create a `TeraResponse` whose HTML is rendered from a source when it is sent. The ETag is computed from the HTML unless it is given.
+    #[inline]
+    pub(crate) fn build_page(
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        etag: Option<EntityTag<'static>>,
+        source: PageSource,
+        blocking: bool,
+    ) -> TeraResponse {
+        let mut response = TeraResponse::with_inner(Some(TeraResponseInner::Page {
+            etag,
+            etag_if_none_match: EtagIfNoneMatch {
+                etag: etag_if_none_match.etag.clone().map(EntityTag::into_owned),
+            },
+            blocking,
+        }));
+
+        response.source = Some(source);
+
+        response
+    }
+
+    /// Create a `TeraResponse` whose HTML is rendered into a streaming body when it is sent.
+    #[inline]
+    pub(crate) fn build_stream(
+        etag_if_none_match: &EtagIfNoneMatch<'_>,
+        etag: Option<EntityTag<'static>>,
+        source: StreamSource,
+    ) -> TeraResponse {
+        TeraResponse::with_inner(Some(TeraResponseInner::PageStream {
+            source,
+            etag,
+            etag_if_none_match: EtagIfNoneMatch {
+                etag: etag_if_none_match.etag.clone().map(EntityTag::into_owned),
+            },
+        }))
+    }
+
+    /// Create a `TeraResponse` which responds **304 Not Modified**, e.g. after checking `IfModifiedSince` before rendering.
+    #[inline]
+    pub const fn not_modified() -> TeraResponse {
+        TeraResponse::with_inner(None)
+    }
+
+    /// Set or remove the `Last-Modified` timestamp of the response. If the request has an `If-Modified-Since` header which is not older than the timestamp, **304 Not Modified** is responded instead.
+    #[inline]
+    pub fn last_modified(mut self, last_modified: Option<SystemTime>) -> TeraResponse {
+        self.last_modified = last_modified;
+
+        #[cfg(debug_assertions)]
+        {
+            self.template_modified = None;
+        }
+
+        self
+    }
+
+    /// Set or remove the policy of the `Cache-Control` header of the response. It overrides the default policy set on the fairing of `TeraResponse`.
+    #[inline]
+    pub fn cache_control(mut self, cache_control: Option<CacheControl>) -> TeraResponse {
+        self.cache_control = cache_control;
+
+        self
+    }
+
+    /// Add a request header to the `Vary` header of the response, because the response depends on it.
+    #[inline]
+    pub(crate) fn vary<S: Into<Cow<'static, str>>>(mut self, header: S) -> TeraResponse {
+        self.vary.push(header.into());
+
+        self
+    }
+
+    /// Set the values which replace their placeholders in the HTML when the response is sent.
+    #[inline]
+    pub(crate) fn placeholders(mut self, placeholders: Placeholders) -> TeraResponse {
+        self.placeholders = placeholders;
+
+        self
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn into_not_modified(mut self) -> TeraResponse {
+        self.inner = None;
+
+        self
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for TeraResponse {
+    #[inline]
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'o> {
+        let mut response = Response::build();
+
+        let placeholders = self.placeholders;
+        let mut last_modified = self.last_modified;
+        let mut cache_control = self.cache_control;
+
+        // a page with a flash message is only for its request, so it is rendered for the request instead of being served from a cache
+        let (mut inner, flashed) = match self.inner {
+            Some(TeraResponseInner::PageStream {
+                source,
+                etag,
+                etag_if_none_match,
+            }) => start_page_stream(request, source, etag, &etag_if_none_match),
+            inner => match self.source {
+                Some(source) => render_page_source(request, &source, inner, self.cache_insert)?,
+                None => (inner, false),
+            },
+        };
+
+        if flashed {
+            last_modified = None;
+            cache_control = Some(flash_cache_control());
+        }
+
+        // values specific to the client, such as CSRF tokens, must neither be shared with other clients nor revalidated with other values
+        if !inner.as_ref().is_some_and(TeraResponseInner::is_json) {
+            let client_values = placeholders.client_values(request);
+
+            if !client_values.is_empty() {
+                last_modified = None;
+
+                if !flashed {
+                    cache_control = Some(client_cache_control(cache_control));
+                }
+
+                let not_modified = match inner.as_mut().and_then(TeraResponseInner::etag_mut) {
+                    Some(etag) => {
+                        let client_etag = mix_client_values(etag, &client_values);
+
+                        *etag = client_etag.to_string();
+
+                        etag_if_none_match(request).weak_eq(&client_etag)
+                    },
+                    None => false,
+                };
+
+                if not_modified {
+                    inner = None;
+                }
+            }
+        }
+
+        if let Some(last_modified) = last_modified {
+            if IfModifiedSince::from_request_headers(request).is_modified(last_modified) {
+                response.raw_header("Last-Modified", format_http_date(last_modified));
+            } else {
+                inner = None;
+            }
+        }
+
+        // a 304 response carries the policy as well, so that caches keep following it
+        if let Some(cache_control) = cache_control {
+            response.raw_header("Cache-Control", cache_control.to_string());
+        }
+
+        if !self.vary.is_empty() {
+            response.raw_header("Vary", self.vary.join(", "));
+        }
+
+        if let Some(inner) = inner {
+            match inner {
+                TeraResponseInner::NotCache {
+                    content,
+                    etag,
+                    content_type,
+                } => {
+                    response.raw_header("Content-Type", content_type);
+                    response.raw_header("Etag", etag);
+
+                    // JSON is not rendered from a template
+                    let content = if content_type == HTML_CONTENT_TYPE {
+                        let replacements = placeholders.generate(request, &mut response);
+
+                        if replacements.is_empty() {
+                            content
+                        } else {
+                            substitute(&content, &replacements)
+                        }
+                    } else {
+                        content
+                    };
+
+                    if is_head_request(request) {
+                        // the body is stripped from the response of a HEAD request, so only its length is needed
+                        response.sized_body(content.len(), Cursor::new(&[] as &[u8]));
+                    } else {
+                        response.sized_body(content.len(), Cursor::new(content));
+                    }
+                },
+                #[cfg(not(debug_assertions))]
+                TeraResponseInner::Cache {
+                    content,
+                    etag,
+                } => {
+                    response.raw_header("Content-Type", HTML_CONTENT_TYPE);
+                    response.raw_header("Etag", etag);
+
+                    let replacements = placeholders.generate(request, &mut response);
+
+                    if is_head_request(request) {
+                        response.sized_body(content.len(), Cursor::new(&[] as &[u8]));
+                    } else if !replacements.is_empty() {
+                        // the cached HTML is shared, so the values are substituted into a copy
+                        let content = substitute(&content, &replacements);
+
+                        response.sized_body(content.len(), Cursor::new(content));
+                    } else {
+                        response.sized_body(content.len(), ArcU8Reader::new(content));
+                    }
+                },
+                TeraResponseInner::Head {
+                    etag,
+                } => {
+                    response.raw_header("Content-Type", HTML_CONTENT_TYPE);
+                    response.raw_header("Etag", etag);
+
+                    placeholders.generate(request, &mut response);
+
+                    // the length of the minified HTML is unknown
+                    response.sized_body(None, UnknownSizeBody);
+                },
+                TeraResponseInner::Page {
+                    ..
+                }
+                | TeraResponseInner::PageStream {
+                    ..
+                } => unreachable!("pages are rendered before they are responded"),
+                TeraResponseInner::Stream {
+                    body,
+                    etag,
+                } => {
+                    response.raw_header("Content-Type", HTML_CONTENT_TYPE);
+
+                    if let Some(etag) = etag {
+                        response.raw_header("Etag", etag);
+                    }
+
+                    let replacements = placeholders.generate(request, &mut response);
+
+                    if is_head_request(request) {
+                        // dropping the body stops rendering
+                        response.sized_body(None, UnknownSizeBody);
+                    } else if replacements.is_empty() {
+                        response.streamed_body(body);
+                    } else {
+                        response.streamed_body(SubstitutingBody::new(body, replacements));
+                    }
+                },
+            }
+        } else {
+            response.status(Status::NotModified);
+        }
+
+        response.ok()
+    }
+}
+
+/// Start rendering a streamed page, with the flash message of its request if there is one. A streamed page with a flash message is sent without its ETag, and a page without it whose ETag matches is not rendered.
+fn start_page_stream(
+    request: &Request<'_>,
+    source: StreamSource,
+    etag: Option<EntityTag<'static>>,
+    etag_if_none_match: &EtagIfNoneMatch<'_>,
+) -> (Option<TeraResponseInner>, bool) {
+    match FlashValue::take(request) {
+        Some(flash) => (
+            Some(TeraResponseInner::Stream {
+                body: source.start(Some(&flash)), etag: None
+            }),
+            true,
+        ),
+        None if etag.as_ref().is_some_and(|etag| etag_if_none_match.weak_eq(etag)) => (None, false),
+        None => (
+            Some(TeraResponseInner::Stream {
+                body: source.start(None),
+                etag: etag.map(|etag| etag.to_string()),
+            }),
+            false,
+        ),
+    }
+}
+
+/// Render a page from its source, with the flash message of its request if there is one. A page which is not rendered yet is rendered without it otherwise, and inserted into the cache if it goes through `tera_response_cache!`.
+fn render_page_source(
+    request: &Request<'_>,
+    source: &PageSource,
+    inner: Option<TeraResponseInner>,
+    cache_insert: Option<CacheInsert>,
+) -> Result<(Option<TeraResponseInner>, bool), Status> {
+    let blocking = matches!(
+        inner,
+        Some(TeraResponseInner::Page {
+            blocking: true,
+            ..
+        })
+    );
+
+    if let Some((html, etag)) = FlashValue::take(request)
+        .and_then(|flash| render_with_flash(request, source, flash, blocking))
+    {
+        return Ok((
+            Some(TeraResponseInner::NotCache {
+                content:      html,
+                etag:         etag.to_string(),
+                content_type: HTML_CONTENT_TYPE,
+            }),
+            true,
+        ));
+    }
+
+    match inner {
+        Some(TeraResponseInner::Page {
+            etag,
+            etag_if_none_match,
+            ..
+        }) => {
+            if etag.as_ref().is_some_and(|etag| etag_if_none_match.weak_eq(etag)) {
+                return Ok((None, false));
+            }
+
+            let (html, html_etag) = render_page(source, blocking)?;
+
+            let etag = etag.unwrap_or(html_etag);
+
+            if let Some(cache_insert) = cache_insert {
+                cache_insert(&html, &etag);
+            }
+
+            if etag_if_none_match.weak_eq(&etag) {
+                Ok((None, false))
+            } else {
+                Ok((
+                    Some(TeraResponseInner::NotCache {
+                        content:      html,
+                        etag:         etag.to_string(),
+                        content_type: HTML_CONTENT_TYPE,
+                    }),
+                    false,
+                ))
+            }
+        },
+        inner => Ok((inner, false)),
+    }
+}
+
+/// Render a page with the flash message of its request. If it fails, the error is logged and the flash message is given back to the client, so that the page is sent without it and the next page shows it.
+fn render_with_flash(
+    request: &Request<'_>,
+    source: &PageSource,
+    flash: FlashValue,
+    blocking: bool,
+) -> Option<(String, EntityTag<'static>)> {
+    let result = if blocking {
+        block_in_place(|| source.render(Some(&flash)))
+    } else {
+        source.render(Some(&flash))
+    };
+
+    match result {
+        Ok(rendered) => Some(rendered),
+        Err(err) => {
+            rocket::error!(
+                "Failed to render the template `{}` with a flash message: {}",
+                source.name(),
+                err
+            );
+
+            flash.restore(request);
+
+            None
+        },
+    }
+}
+
+/// Render a page without a flash message. If it fails, the error is logged and **500 Internal Server Error** is responded.
+fn render_page(
+    source: &PageSource,
+    blocking: bool,
+) -> Result<(String, EntityTag<'static>), Status> {
+    let result =
+        if blocking { block_in_place(|| source.render(None)) } else { source.render(None) };
+
+    result.map_err(|err| {
+        rocket::error!("Failed to render the template `{}`: {}", source.name(), err);
+
+        Status::InternalServerError
+    })
+}
@@ -0,0 +1,60 @@
+use std::error::Error;
+
+use serde::Serialize;
+use tera::{Context, Error as TeraError, Tera};
+
+use crate::placeholder::Placeholders;
+
+type ContextProvider = Box<dyn Fn() -> Result<Context, TeraError> + Send + Sync + 'static>;
+
+/// An entry to be rendered into the cache before the first request.
+pub(crate) struct WarmEntry {
+    pub(crate) key:  String,
+    pub(crate) name: String,
+    context:         ContextProvider,
+}
+
+impl WarmEntry {
+    #[inline]
+    pub(crate) fn new<V: Serialize, F: Fn() -> V + Send + Sync + 'static>(
+        key: String,
+        name: String,
+        f: F,
+    ) -> WarmEntry {
+        WarmEntry {
+            key,
+            name,
+            context: Box::new(move || Context::from_serialize(f())),
+        }
+    }
+
+    /// Render the template of this entry together with the context it is rendered with, or get a message explaining why it cannot be rendered.
+    pub(crate) fn render(
+        &self,
+        tera: &Tera,
+        placeholders: &Placeholders,
+    ) -> Result<(String, Context), String> {
+        let result = (self.context)().and_then(|mut context| {
+            placeholders.insert_into(&mut context);
+
+            tera.render(&self.name, &context).map(|html| (html, context))
+        });
+
+        result.map_err(|err| {
+            let mut message = format!(
+                "Failed to warm the cache entry `{}` with the template `{}`: {}",
+                self.key, self.name, err
+            );
+
+            let mut source = err.source();
+
+            while let Some(err) = source {
+                message.push_str(&format!(": {}", err));
+
+                source = err.source();
+            }
+
+            message
+        })
+    }
+}
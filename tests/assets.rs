@@ -0,0 +1,39 @@
+use rocket::{http::Status, local::blocking::Client};
+use rocket_include_tera::*;
+use serde_json::json;
+
+#[test]
+fn assets() {
+    let mut assets = TeraAssets::new("/assets");
+
+    tera_assets_initialize!(
+        assets,
+        "css/style.css" => "examples/assets/css/style.css"
+    );
+
+    let url = assets.url("css/style.css").unwrap();
+
+    assert!(url.starts_with("/assets/css/style."));
+    assert!(assets.url("css/missing.css").is_none());
+
+    let rocket = rocket::build()
+        .attach(
+            TeraResponse::fairing(|tera| {
+                tera.add_raw_template("link", r#"{{ asset(path="css/style.css") }}"#).unwrap();
+            })
+            .assets(assets.clone()),
+        )
+        .mount("/assets", assets);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    let cm = client.rocket().state::<TeraContextManager>().unwrap();
+
+    assert_eq!(url, cm.render("link", json!({})));
+
+    let response = client.get(url).dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert!(response.headers().get_one("Cache-Control").is_some());
+    assert!(response.into_string().unwrap().contains("font-family"));
+}
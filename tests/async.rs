@@ -0,0 +1,42 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{http::Status, local::asynchronous::Client, State};
+use rocket_include_tera::*;
+use serde_json::json;
+
+#[get("/")]
+async fn index(
+    cm: &State<TeraContextManager>,
+    etag_if_none_match: EtagIfNoneMatch<'_>,
+) -> TeraResponse {
+    let data = json!({
+        "title": "Title",
+        "placeholder": "Hello",
+        "id": 0,
+    });
+
+    tera_response!(async disable_minify cm, etag_if_none_match, "index2", data)
+}
+
+#[rocket::async_test]
+async fn build_async() {
+    let rocket = rocket::build()
+        .attach(tera_resources_initializer!(
+            "index2" => "examples/views/index2.tera"
+        ))
+        .mount("/", routes![index]);
+
+    let client = Client::tracked(rocket).await.unwrap();
+
+    let response = client.get("/").dispatch().await;
+
+    assert_eq!(Status::Ok, response.status());
+    assert!(response.into_string().await.unwrap().contains("input-1"));
+
+    let cm = client.rocket().state::<TeraContextManager>().unwrap();
+
+    let html = cm.render_async("index2", json!({ "title": "", "placeholder": "", "id": 1 })).await;
+
+    assert!(html.contains("input-2"));
+}
@@ -0,0 +1,42 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{http::Status, local::blocking::Client, State};
+use rocket_include_tera::*;
+use serde_json::json;
+
+#[get("/<id>")]
+fn auto(
+    cm: &State<TeraContextManager>,
+    etag_if_none_match: EtagIfNoneMatch,
+    id: usize,
+) -> TeraResponse {
+    tera_response_cache!(auto cm, etag_if_none_match, "page", json!({ "id": id }))
+}
+
+#[test]
+fn auto_key() {
+    let rocket = rocket::build()
+        .attach(TeraResponse::fairing(|tera| {
+            tera.add_raw_template("page", "<p>{{ id }}</p>").unwrap();
+        }))
+        .mount("/", routes![auto]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    for id in [1, 1, 2] {
+        let response = client.get(format!("/{}", id)).dispatch();
+
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(format!("<p>{}</p>", id), response.into_string().unwrap());
+    }
+
+    let cm = client.rocket().state::<TeraContextManager>().unwrap();
+
+    if cfg!(debug_assertions) {
+        assert!(cm.cache_keys().is_empty());
+    } else {
+        assert_eq!(2, cm.cache_keys().len());
+        assert_eq!(1, cm.cache_stats().hits);
+    }
+}
@@ -0,0 +1,54 @@
+#[macro_use]
+extern crate rocket;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rocket::{http::Status, local::blocking::Client, State};
+use rocket_include_tera::*;
+use serde_json::json;
+
+static GENERATED: AtomicUsize = AtomicUsize::new(0);
+
+#[get("/")]
+fn index(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response_cache!(cm, etag_if_none_match, "index-2", {
+        GENERATED.fetch_add(1, Ordering::SeqCst);
+
+        tera_response!(
+            cm,
+            EtagIfNoneMatch::default(),
+            "index2",
+            json!({
+                "title": "Title",
+                "placeholder": "Hello",
+                "id": 0,
+            })
+        )
+    })
+}
+
+#[test]
+fn cache() {
+    let rocket = rocket::build()
+        .attach(
+            tera_resources_initializer!(
+                100;
+                "index2" => "examples/views/index2.tera"
+            )
+            .cache_shards(4),
+        )
+        .mount("/", routes![index]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    for _ in 0..2 {
+        let response = client.get("/").dispatch();
+
+        assert_eq!(Status::Ok, response.status());
+        assert!(response.into_string().unwrap().contains("input-1"));
+    }
+
+    let expected = if cfg!(debug_assertions) { 2 } else { 1 };
+
+    assert_eq!(expected, GENERATED.load(Ordering::SeqCst));
+}
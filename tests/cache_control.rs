@@ -0,0 +1,95 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+    State,
+};
+use rocket_include_tera::*;
+use serde_json::json;
+
+#[get("/")]
+fn index(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response!(cm, etag_if_none_match, "index", json!({ "title": "Hello" }))
+}
+
+#[get("/private")]
+fn private(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    let cache_control = CacheControl::Private {
+        max_age: 60
+    };
+
+    tera_response!(cm, etag_if_none_match, "index", json!({ "title": "Hello" }))
+        .cache_control(Some(cache_control))
+}
+
+#[get("/cached")]
+fn cached(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response_cache!(cache_control CacheControl::NoCache => cm, etag_if_none_match, "cached", {
+        tera_response!(cm, EtagIfNoneMatch::default(), "index", json!({ "title": "Hello" }))
+    })
+}
+
+#[get("/generated")]
+fn generated(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response_cache!(cm, etag_if_none_match, "generated", {
+        tera_response!(cm, EtagIfNoneMatch::default(), "index", json!({ "title": "Hello" }))
+            .cache_control(Some(CacheControl::NoStore))
+    })
+}
+
+#[test]
+fn cache_control() {
+    let rocket = rocket::build()
+        .attach(
+            TeraResponse::fairing(|tera| {
+                tera.add_raw_template("index", "<p>{{ title }}</p>").unwrap();
+            })
+            .cache_control(CacheControl::Public {
+                max_age:                60,
+                s_maxage:               Some(3600),
+                stale_while_revalidate: Some(30),
+            }),
+        )
+        .mount("/", routes![index, private, cached, generated]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    let response = client.get("/").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!(
+        Some("public, max-age=60, s-maxage=3600, stale-while-revalidate=30"),
+        response.headers().get_one("Cache-Control")
+    );
+
+    let etag = response.headers().get_one("ETag").unwrap().to_string();
+
+    let response = client.get("/").header(Header::new("If-None-Match", etag)).dispatch();
+
+    assert_eq!(Status::NotModified, response.status());
+    assert_eq!(
+        Some("public, max-age=60, s-maxage=3600, stale-while-revalidate=30"),
+        response.headers().get_one("Cache-Control")
+    );
+
+    let response = client.get("/private").dispatch();
+
+    assert_eq!(Some("private, max-age=60"), response.headers().get_one("Cache-Control"));
+
+    for _ in 0..2 {
+        let response = client.get("/cached").dispatch();
+
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(Some("no-cache"), response.headers().get_one("Cache-Control"));
+    }
+
+    // the policy set by the constructor is kept for the responses of the cache
+    for _ in 0..2 {
+        let response = client.get("/generated").dispatch();
+
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(Some("no-store"), response.headers().get_one("Cache-Control"));
+    }
+}
@@ -0,0 +1,69 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+};
+use rocket_include_tera::*;
+
+#[get("/fail")]
+fn fail() -> Status {
+    Status::InternalServerError
+}
+
+#[get("/unprocessable")]
+fn unprocessable() -> Status {
+    Status::UnprocessableEntity
+}
+
+fn client(attach: bool) -> Client {
+    let mut rocket = rocket::build()
+        .mount("/", routes![fail, unprocessable])
+        .register("/", tera_catchers!("404" => "not_found", 422 => "broken", default => "error"));
+
+    if attach {
+        rocket = rocket.attach(TeraResponse::fairing(|tera| {
+            tera.add_raw_template("not_found", "Missing: {{ path }}").unwrap();
+            tera.add_raw_template("error", "{{ status }} {{ reason }}").unwrap();
+            tera.add_raw_template("broken", "{{ missing }}").unwrap();
+        }));
+    }
+
+    Client::tracked(rocket).unwrap()
+}
+
+#[test]
+fn catchers() {
+    let client = client(true);
+
+    let response = client.get("/missing").dispatch();
+
+    assert_eq!(Status::NotFound, response.status());
+    assert_eq!(Some(ContentType::HTML), response.content_type());
+    assert_eq!(Some("no-store"), response.headers().get_one("Cache-Control"));
+    assert!(response.headers().get_one("Last-Modified").is_none());
+    assert_eq!("Missing: /missing", response.into_string().unwrap());
+
+    let response = client.get("/fail").dispatch();
+
+    assert_eq!(Status::InternalServerError, response.status());
+    assert_eq!("500 Internal Server Error", response.into_string().unwrap());
+
+    // a template which cannot be rendered falls back to a plain text page instead of a panic
+    let response = client.get("/unprocessable").dispatch();
+
+    assert_eq!(Status::UnprocessableEntity, response.status());
+    assert_eq!(Some(ContentType::Plain), response.content_type());
+    assert_eq!("422 Unprocessable Entity", response.into_string().unwrap());
+}
+
+#[test]
+fn catchers_without_manager() {
+    let client = client(false);
+
+    let response = client.get("/missing").dispatch();
+
+    assert_eq!(Status::NotFound, response.status());
+    assert_eq!("404 Not Found", response.into_string().unwrap());
+}
@@ -0,0 +1,94 @@
+#[macro_use]
+extern crate rocket;
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+    State,
+};
+use rocket_include_tera::*;
+use serde_json::json;
+
+static RENDERS: AtomicUsize = AtomicUsize::new(0);
+
+#[get("/<title>")]
+fn index(
+    cm: &State<TeraContextManager>,
+    etag_if_none_match: EtagIfNoneMatch,
+    title: &str,
+) -> TeraResponse {
+    cm.build_with_context_etag(&etag_if_none_match, true, "index", json!({ "title": title }))
+}
+
+#[test]
+fn context_etag() {
+    let rocket = rocket::build()
+        .attach(TeraResponse::fairing(|tera| {
+            tera.register_function("count", |_: &HashMap<String, tera::Value>| {
+                RENDERS.fetch_add(1, Ordering::SeqCst);
+
+                Ok(tera::Value::Null)
+            });
+
+            tera.add_raw_template("index", "<h1>{{ title }}</h1>{{ count() }}").unwrap();
+        }))
+        .mount("/", routes![index]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    let response = client.get("/hello").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!(1, RENDERS.load(Ordering::SeqCst));
+
+    let etag = response.headers().get_one("ETag").unwrap().to_string();
+
+    assert_eq!("<h1>hello</h1>", response.into_string().unwrap());
+
+    let response =
+        client.get("/hello").header(Header::new("If-None-Match", etag.clone())).dispatch();
+
+    assert_eq!(Status::NotModified, response.status());
+    assert_eq!(1, RENDERS.load(Ordering::SeqCst));
+
+    let response = client.get("/world").header(Header::new("If-None-Match", etag)).dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!(2, RENDERS.load(Ordering::SeqCst));
+    assert_eq!("<h1>world</h1>", response.into_string().unwrap());
+}
+
+#[get("/")]
+fn page(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    cm.build_with_context_etag(&etag_if_none_match, true, "page", json!({}))
+}
+
+#[test]
+fn cache_version() {
+    let etags = ["1", "2"].map(|version| {
+        let rocket = rocket::build()
+            .attach(
+                TeraResponse::fairing(|tera| {
+                    tera.add_raw_template("page", "<p>Page</p>").unwrap();
+                })
+                .cache_version(version),
+            )
+            .mount("/", routes![page]);
+
+        let client = Client::tracked(rocket).unwrap();
+
+        let response = client.get("/").dispatch();
+
+        response.headers().get_one("ETag").unwrap().to_string()
+    });
+
+    // the raw template is not hashed, so only the given version tells the ETags apart
+    if !cfg!(debug_assertions) {
+        assert_ne!(etags[0], etags[1]);
+    }
+}
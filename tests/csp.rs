@@ -0,0 +1,95 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::{Client, LocalResponse},
+    State,
+};
+use rocket_include_tera::*;
+use serde_json::json;
+
+#[get("/")]
+fn index(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response!(disable_minify cm, etag_if_none_match, "index", json!({ "title": "Hello" }))
+}
+
+#[get("/cached")]
+fn cached(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response_cache!(cm, etag_if_none_match, "cached", {
+        let context = json!({ "title": "Hello" });
+
+        tera_response!(disable_minify cm, EtagIfNoneMatch::default(), "index", context)
+    })
+}
+
+#[get("/stream")]
+fn stream(cm: &State<TeraContextManager>) -> TeraResponse {
+    cm.build_stream("index", json!({ "title": "Hello" }))
+}
+
+/// Get the nonce from the header, and check that the body uses it.
+fn nonce_of(response: LocalResponse<'_>) -> String {
+    assert_eq!(Status::Ok, response.status());
+
+    let policy = response.headers().get_one("Content-Security-Policy").unwrap().to_string();
+
+    let nonce = policy
+        .strip_prefix("script-src 'nonce-")
+        .and_then(|nonce| nonce.strip_suffix('\''))
+        .unwrap()
+        .to_string();
+
+    assert_eq!(32, nonce.len());
+    assert_eq!(
+        format!("<script nonce=\"{}\">Hello</script>", nonce),
+        response.into_string().unwrap()
+    );
+
+    nonce
+}
+
+#[test]
+fn csp() {
+    let rocket = rocket::build()
+        .attach(
+            TeraResponse::fairing(|tera| {
+                tera.add_raw_template(
+                    "index",
+                    "<script nonce=\"{{ csp_nonce }}\">{{ title }}</script>",
+                )
+                .unwrap();
+            })
+            .content_security_policy("script-src 'nonce-{nonce}'"),
+        )
+        .mount("/", routes![index, cached, stream]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    for uri in ["/", "/cached", "/stream"] {
+        let first = nonce_of(client.get(uri).dispatch());
+        let second = nonce_of(client.get(uri).dispatch());
+
+        assert_ne!(first, second);
+    }
+
+    let response = client.get("/cached").dispatch();
+
+    let etag = response.headers().get_one("ETag").unwrap().to_string();
+
+    // the ETag is computed from the HTML with the placeholder, so it does not change with the nonce
+    let response = client.get("/cached").header(Header::new("If-None-Match", etag)).dispatch();
+
+    assert_eq!(Status::NotModified, response.status());
+    assert_eq!(None, response.headers().get_one("Content-Security-Policy"));
+
+    // HTML rendered outside of a response never has the placeholder
+    let cm = client.rocket().state::<TeraContextManager>().unwrap();
+
+    let first = cm.render("index", json!({ "title": "Hello" }));
+    let second = cm.render("index", json!({ "title": "Hello" }));
+
+    assert_ne!(first, second);
+    assert!(first.starts_with("<script nonce=\""));
+    assert_eq!(first.len(), second.len());
+}
@@ -0,0 +1,163 @@
+#![cfg(feature = "csrf")]
+
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::blocking::Client,
+    Config, State,
+};
+use rocket_include_tera::*;
+use serde_json::json;
+
+#[derive(FromForm)]
+struct Message {
+    text: String,
+}
+
+#[get("/")]
+fn index(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response_cache!(cm, etag_if_none_match, "index", {
+        tera_response!(disable_minify cm, EtagIfNoneMatch::default(), "index", json!({}))
+    })
+}
+
+#[get("/token")]
+fn token(token: CsrfToken<'_>) -> String {
+    token.as_str().to_string()
+}
+
+#[post("/", data = "<message>")]
+fn submit(message: CsrfForm<Message>) -> String {
+    message.into_inner().text
+}
+
+fn new_client() -> Client {
+    let secret_key = (1..=32u8).map(|i| format!("{:02x}", i)).collect::<String>();
+
+    let rocket = rocket::custom(Config::figment().merge(("secret_key", secret_key)))
+        .attach(
+            TeraResponse::fairing(|tera| {
+                tera.add_raw_template("index", "<form>{{ csrf_field() }}</form>{{ csrf_token }}")
+                    .unwrap();
+            })
+            .csrf(),
+        )
+        .mount("/", routes![index, token, submit]);
+
+    Client::tracked(rocket).unwrap()
+}
+
+#[test]
+fn csrf() {
+    let client = new_client();
+
+    let token = client.get("/token").dispatch().into_string().unwrap();
+
+    assert_eq!(32, token.len());
+
+    // the cookie is kept by the client, so the token does not change, even for the cached page
+    for _ in 0..2 {
+        assert_eq!(
+            format!(
+                "<form><input type=\"hidden\" name=\"csrf_token\" value=\"{}\"></form>{}",
+                token, token
+            ),
+            client.get("/").dispatch().into_string().unwrap()
+        );
+    }
+
+    let response = client
+        .post("/")
+        .header(ContentType::Form)
+        .body(format!("text=Hello&csrf_token={}", token))
+        .dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("Hello", response.into_string().unwrap());
+
+    let response = client
+        .post("/")
+        .header(ContentType::Form)
+        .body(format!("text=Hello&csrf_token={}", "0".repeat(32)))
+        .dispatch();
+
+    assert_eq!(Status::Forbidden, response.status());
+
+    let response = client.post("/").header(ContentType::Form).body("text=Hello").dispatch();
+
+    assert_eq!(Status::Forbidden, response.status());
+
+    // another client gets another token
+    let other = new_client();
+
+    let other_token = other.get("/token").dispatch().into_string().unwrap();
+
+    assert_ne!(token, other_token);
+
+    assert!(other.get("/").dispatch().into_string().unwrap().ends_with(&other_token));
+}
+
+#[test]
+fn csrf_revalidation() {
+    let client = new_client();
+
+    let response = client.get("/").dispatch();
+
+    assert_eq!(Some("private, no-cache"), response.headers().get_one("Cache-Control"));
+    assert_eq!(None, response.headers().get_one("Last-Modified"));
+
+    let etag = response.headers().get_one("ETag").unwrap().to_string();
+
+    response.into_string().unwrap();
+
+    let response = client.get("/").header(Header::new("If-None-Match", etag.clone())).dispatch();
+
+    assert_eq!(Status::NotModified, response.status());
+
+    // a client whose cookie has expired gets a new token instead of a 304 with the old one
+    let expired = new_client();
+
+    let response = expired.get("/").header(Header::new("If-None-Match", etag.clone())).dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert!(response.cookies().iter().next().is_some());
+    assert_ne!(Some(etag.as_str()), response.headers().get_one("ETag"));
+}
+
+#[test]
+fn csrf_multipart() {
+    let client = new_client();
+
+    let token = client.get("/token").dispatch().into_string().unwrap();
+
+    let token_part = format!(
+        "--BOUNDARY\r\nContent-Disposition: form-data; name=\"csrf_token\"\r\n\r\n{}\r\n",
+        token
+    );
+
+    let text_part = "--BOUNDARY\r\nContent-Disposition: form-data; name=\"text\"; \
+                     filename=\"text.txt\"\r\nContent-Type: text/plain\r\n\r\nHello\r\n";
+
+    let multipart =
+        ContentType::new("multipart", "form-data").with_params(("boundary", "BOUNDARY"));
+
+    let response = client
+        .post("/")
+        .header(multipart.clone())
+        .body(format!("{}{}--BOUNDARY--\r\n", token_part, text_part))
+        .dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("Hello", response.into_string().unwrap());
+
+    // data fields before the token are never accepted
+    let response = client
+        .post("/")
+        .header(multipart)
+        .body(format!("{}{}--BOUNDARY--\r\n", text_part, token_part))
+        .dispatch();
+
+    assert_eq!(Status::Forbidden, response.status());
+}
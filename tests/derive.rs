@@ -0,0 +1,131 @@
+#![cfg(feature = "derive")]
+
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+use rocket_include_tera::*;
+use serde::Serialize;
+
+#[derive(Serialize, TeraTemplate)]
+#[template(name = "index", path = "examples/views/index.tera", minify = false)]
+struct Index {
+    title: &'static str,
+    body:  &'static str,
+}
+
+#[derive(Serialize, TeraTemplate)]
+#[template(name = "index2", path = "examples/views/index2.tera")]
+struct Index2 {
+    title: &'static str,
+    #[serde(rename = "placeholder")]
+    hint:  &'static str,
+    id:    u32,
+}
+
+#[derive(Serialize, TeraTemplate)]
+#[template(
+    name = "script",
+    path = "examples/views/script.tera",
+    minify = false,
+    provided(csp_nonce)
+)]
+struct Script {
+    message: &'static str,
+}
+
+struct Admin;
+
+#[derive(Serialize, TeraTemplate)]
+#[template(name = "admin", path = "examples/views/index2.tera", tagged = Admin)]
+struct AdminIndex {
+    title:       &'static str,
+    placeholder: &'static str,
+    id:          u32,
+}
+
+#[get("/")]
+fn index() -> Index {
+    Index {
+        title: "Title", body: "Hello, world!"
+    }
+}
+
+#[get("/2")]
+fn index_2() -> Index2 {
+    Index2 {
+        title: "Title", hint: "Hello", id: 0
+    }
+}
+
+#[get("/script")]
+fn script() -> Script {
+    Script {
+        message: "Hello"
+    }
+}
+
+#[get("/admin")]
+fn admin() -> AdminIndex {
+    AdminIndex {
+        title: "Admin", placeholder: "Hello, admin!", id: 1
+    }
+}
+
+#[test]
+fn derive() {
+    assert_eq!("index", Index::NAME);
+
+    let mut assets = TeraAssets::new("/assets");
+
+    tera_assets_initialize!(
+        assets,
+        "css/style.css" => "examples/assets/css/style.css"
+    );
+
+    let rocket = rocket::build()
+        .attach(
+            TeraResponse::fairing(|_| {})
+                .assets(assets)
+                .content_security_policy("script-src 'nonce-{nonce}'"),
+        )
+        .attach(TeraResponse::fairing(|_| {}).tagged::<Admin>())
+        .mount("/", routes![index, index_2, script, admin]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    let response = client.get("/").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert!(response.into_string().unwrap().contains("Hello, world!"));
+
+    let response = client.get("/2").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+
+    let etag = response.headers().get_one("Etag").unwrap().to_string();
+
+    assert!(response.into_string().unwrap().contains("input-1"));
+
+    let response = client.get("/2").header(Header::new("If-None-Match", etag)).dispatch();
+
+    assert_eq!(Status::NotModified, response.status());
+
+    let response = client.get("/script").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+
+    let policy = response.headers().get_one("Content-Security-Policy").unwrap().to_string();
+
+    let nonce = policy.trim_start_matches("script-src 'nonce-").trim_end_matches('\'');
+
+    assert!(response.into_string().unwrap().contains(&format!("nonce=\"{}\"", nonce)));
+
+    let response = client.get("/admin").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert!(response.into_string().unwrap().contains("input-2"));
+}
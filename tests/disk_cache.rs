@@ -0,0 +1,157 @@
+#[macro_use]
+extern crate rocket;
+
+use std::{
+    env, fs,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use rocket::{http::Status, local::blocking::Client, Build, Rocket, State};
+use rocket_include_tera::*;
+use serde_json::json;
+
+static GENERATED: AtomicUsize = AtomicUsize::new(0);
+
+#[get("/")]
+fn index(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response_cache!(cm, etag_if_none_match, "index-2", {
+        GENERATED.fetch_add(1, Ordering::SeqCst);
+
+        tera_response!(
+            cm,
+            EtagIfNoneMatch::default(),
+            "index2",
+            json!({
+                "title": "Title",
+                "placeholder": "Hello",
+                "id": 0,
+            })
+        )
+    })
+}
+
+fn rocket(directory: &Path) -> Rocket<Build> {
+    rocket::build()
+        .attach(
+            tera_resources_initializer!(
+                "index2" => "examples/views/index2.tera"
+            )
+            .disk_cache(directory),
+        )
+        .mount("/", routes![index])
+}
+
+#[test]
+fn disk_cache() {
+    let directory =
+        env::temp_dir().join(format!("rocket-include-tera-disk-cache-{}", std::process::id()));
+
+    // files of users in the same directory are never removed
+    let unrelated_directory = directory.join("unrelated");
+
+    fs::create_dir_all(&unrelated_directory).unwrap();
+
+    let mut other_directories = Vec::new();
+
+    let mut etags = Vec::new();
+
+    // every client is a restart, which empties the memory
+    for _ in 0..2 {
+        let client = Client::tracked(rocket(&directory)).unwrap();
+
+        // the directories of other versions are kept for the processes of other deploys
+        if other_directories.is_empty() {
+            if let Ok(entries) = fs::read_dir(directory.join("rocket-include-tera")) {
+                for entry in entries {
+                    let other_directory = entry.unwrap().path().join("other");
+
+                    fs::create_dir_all(&other_directory).unwrap();
+
+                    other_directories.push(other_directory);
+                }
+            }
+        }
+
+        let response = client.get("/").dispatch();
+
+        assert_eq!(Status::Ok, response.status());
+
+        etags.push(response.headers().get_one("ETag").unwrap().to_string());
+
+        assert!(response.into_string().unwrap().contains("input-1"));
+    }
+
+    assert_eq!(etags[0], etags[1]);
+
+    if cfg!(debug_assertions) {
+        assert_eq!(2, GENERATED.load(Ordering::SeqCst));
+    } else {
+        assert_eq!(1, GENERATED.load(Ordering::SeqCst));
+        assert_eq!(1, other_directories.len());
+        assert!(other_directories[0].exists());
+    }
+
+    assert!(unrelated_directory.exists());
+
+    #[cfg(not(debug_assertions))]
+    {
+        let client = Client::tracked(rocket(&directory)).unwrap();
+
+        let cm = client.rocket().state::<TeraContextManager>().unwrap();
+
+        assert!(cm.contains_key("index-2"));
+
+        cm.clear_cache();
+
+        assert!(!cm.contains_key("index-2"));
+
+        client.get("/").dispatch();
+
+        assert_eq!(2, GENERATED.load(Ordering::SeqCst));
+    }
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+static GENERATED_VERSIONED: AtomicUsize = AtomicUsize::new(0);
+
+#[get("/")]
+fn versioned(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response_cache!(cm, etag_if_none_match, "page", {
+        GENERATED_VERSIONED.fetch_add(1, Ordering::SeqCst);
+
+        tera_response!(cm, EtagIfNoneMatch::default(), "page", json!({}))
+    })
+}
+
+#[test]
+fn cache_version() {
+    let directory =
+        env::temp_dir().join(format!("rocket-include-tera-cache-version-{}", std::process::id()));
+
+    // every client is a restart, and the source of the raw template is not hashed, so only the given version tells the entries apart
+    for (version, expected) in [("1", 1), ("1", 1), ("2", 2)] {
+        let rocket = rocket::build()
+            .attach(
+                TeraResponse::fairing(|tera| {
+                    tera.add_raw_template("page", "<p>Page</p>").unwrap();
+                })
+                .disk_cache(&directory)
+                .cache_version(version),
+            )
+            .mount("/", routes![versioned]);
+
+        let client = Client::tracked(rocket).unwrap();
+
+        let response = client.get("/").dispatch();
+
+        assert_eq!(Status::Ok, response.status());
+
+        if !cfg!(debug_assertions) {
+            assert_eq!(expected, GENERATED_VERSIONED.load(Ordering::SeqCst));
+        }
+    }
+
+    fs::remove_dir_all(directory).unwrap_or_default();
+}
@@ -0,0 +1,45 @@
+#[macro_use]
+extern crate rocket;
+
+use std::{collections::BTreeMap, env, fs};
+
+use rocket::State;
+use rocket_include_tera::*;
+use serde_json::json;
+
+#[get("/")]
+fn index(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response!(cm, etag_if_none_match, "page", json!({ "title": "Home" }))
+}
+
+#[rocket::async_test]
+async fn export() {
+    let output_dir =
+        env::temp_dir().join(format!("rocket-include-tera-export-{}", std::process::id()));
+
+    let rocket = rocket::build()
+        .attach(TeraResponse::fairing(|tera| {
+            tera.add_raw_template("page", "<p>{{ title }}</p>").unwrap();
+        }))
+        .mount("/", routes![index]);
+
+    let manifest = StaticExport::new(&output_dir)
+        .template("about/index.html", "page", json!({ "title": "About" }))
+        .route("index.html", "/")
+        .run(rocket)
+        .await
+        .unwrap();
+
+    assert_eq!("<p>About</p>", fs::read_to_string(output_dir.join("about/index.html")).unwrap());
+    assert_eq!("<p>Home</p>", fs::read_to_string(output_dir.join("index.html")).unwrap());
+
+    let written: BTreeMap<String, String> =
+        serde_json::from_str(&fs::read_to_string(output_dir.join(EXPORT_MANIFEST_NAME)).unwrap())
+            .unwrap();
+
+    assert_eq!(manifest, written);
+    assert_eq!(2, manifest.len());
+    assert_ne!(manifest["index.html"], manifest["about/index.html"]);
+
+    fs::remove_dir_all(output_dir).unwrap();
+}
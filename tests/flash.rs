@@ -0,0 +1,230 @@
+#[macro_use]
+extern crate rocket;
+
+use std::{env, fs};
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+    response::{Flash, Redirect},
+    State,
+};
+use rocket_include_tera::*;
+use serde_json::json;
+
+const TEMPLATE: &str =
+    "{% if flash %}{{ flash.kind }}: {{ flash.message }}{% else %}{{ title }}{% endif %}";
+
+const PAGE_TEMPLATE: &str =
+    "<main>{% block content %}{% include \"index\" %}{% endblock content %}</main>";
+
+// a flash message makes it fail, because it has no such field
+const BROKEN_TEMPLATE: &str = "{% if flash %}{{ flash.title }}{% else %}{{ title }}{% endif %}";
+
+#[get("/")]
+fn index(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response!(cm, etag_if_none_match, "index", json!({ "title": "Hello" }))
+}
+
+#[get("/cached")]
+fn cached(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response_cache!(cm, etag_if_none_match, "cached", {
+        let context = json!({ "title": "Hello" });
+
+        tera_response!(cm, EtagIfNoneMatch::default(), "index", context)
+    })
+}
+
+#[get("/context_etag")]
+fn context_etag(
+    cm: &State<TeraContextManager>,
+    etag_if_none_match: EtagIfNoneMatch,
+) -> TeraResponse {
+    cm.build_with_context_etag(&etag_if_none_match, true, "index", json!({ "title": "Hello" }))
+}
+
+#[get("/localized")]
+fn localized(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    cm.build_localized(&["de"], &etag_if_none_match, true, "index", json!({ "title": "Hello" }))
+}
+
+#[get("/block")]
+fn block(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    cm.build_block(&etag_if_none_match, true, "page", "content", json!({ "title": "Hello" }))
+}
+
+#[get("/async")]
+async fn async_page(
+    cm: &State<TeraContextManager>,
+    etag_if_none_match: EtagIfNoneMatch<'_>,
+) -> TeraResponse {
+    tera_response!(async cm, etag_if_none_match, "index", json!({ "title": "Hello" }))
+}
+
+#[get("/stream")]
+fn stream(cm: &State<TeraContextManager>) -> TeraResponse {
+    cm.build_stream("index", json!({ "title": "Hello" }))
+}
+
+#[get("/broken")]
+fn broken(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response!(cm, etag_if_none_match, "broken", json!({ "title": "Hello" }))
+}
+
+#[get("/save?<to>")]
+fn save(to: &str) -> Flash<Redirect> {
+    Flash::success(Redirect::to(format!("/{}", to)), "Saved")
+}
+
+fn client() -> Client {
+    client_with(|fairing| fairing)
+}
+
+fn client_with(f: impl FnOnce(TeraResponseFairing) -> TeraResponseFairing) -> Client {
+    let rocket = rocket::build()
+        .attach(
+            f(TeraResponse::fairing(|tera| {
+                tera.add_raw_templates(vec![
+                    ("index", TEMPLATE),
+                    ("page", PAGE_TEMPLATE),
+                    ("broken", BROKEN_TEMPLATE),
+                ])
+                .unwrap();
+            }))
+            .cache_control(CacheControl::Public {
+                max_age:                3600,
+                s_maxage:               None,
+                stale_while_revalidate: None,
+            }),
+        )
+        .mount("/", routes![
+            index,
+            cached,
+            context_etag,
+            localized,
+            block,
+            async_page,
+            stream,
+            broken,
+            save
+        ]);
+
+    Client::tracked(rocket).unwrap()
+}
+
+#[test]
+fn flash() {
+    let client = client();
+
+    assert_eq!(Status::SeeOther, client.get("/save?to=").dispatch().status());
+
+    let response = client.get("/").dispatch();
+
+    assert!(response.headers().get_one("Last-Modified").is_none());
+    assert_eq!(Some("private, no-store"), response.headers().get_one("Cache-Control"));
+    assert_eq!("success: Saved", response.into_string().unwrap());
+
+    // the flash message has been consumed
+    let response = client.get("/").dispatch();
+
+    assert_eq!(Some("public, max-age=3600"), response.headers().get_one("Cache-Control"));
+
+    let etag = response.headers().get_one("ETag").unwrap().to_string();
+
+    assert_eq!("Hello", response.into_string().unwrap());
+
+    // the page without the flash message is not revalidated
+    client.get("/save?to=").dispatch();
+
+    let response = client.get("/").header(Header::new("If-None-Match", etag)).dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("success: Saved", response.into_string().unwrap());
+}
+
+#[test]
+fn flash_not_cached() {
+    let client = client();
+
+    assert_eq!("Hello", client.get("/cached").dispatch().into_string().unwrap());
+
+    client.get("/save?to=cached").dispatch();
+
+    let response = client.get("/cached").dispatch();
+
+    assert!(response.headers().get_one("Last-Modified").is_none());
+    assert_eq!(Some("private, no-store"), response.headers().get_one("Cache-Control"));
+    assert_eq!("success: Saved", response.into_string().unwrap());
+
+    // the page with the flash message has not replaced the cached one
+    assert_eq!("Hello", client.get("/cached").dispatch().into_string().unwrap());
+}
+
+#[test]
+fn flash_every_builder() {
+    let client = client();
+
+    for path in ["context_etag", "localized", "block", "async", "stream"] {
+        let response = client.get(format!("/{}", path)).dispatch();
+
+        let etag = response.headers().get_one("ETag").map(|etag| etag.to_string());
+
+        assert_eq!("Hello", response.into_string().unwrap(), "{}", path);
+
+        client.get(format!("/save?to={}", path)).dispatch();
+
+        let mut request = client.get(format!("/{}", path));
+
+        if let Some(etag) = etag {
+            request = request.header(Header::new("If-None-Match", etag));
+        }
+
+        let response = request.dispatch();
+
+        assert_eq!(Status::Ok, response.status(), "{}", path);
+        assert_eq!(Some("private, no-store"), response.headers().get_one("Cache-Control"));
+        assert_eq!("success: Saved", response.into_string().unwrap(), "{}", path);
+    }
+}
+
+#[test]
+fn flash_render_failure() {
+    let client = client();
+
+    client.get("/save?to=broken").dispatch();
+
+    // the page is sent without the flash message
+    let response = client.get("/broken").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!(Some("public, max-age=3600"), response.headers().get_one("Cache-Control"));
+    assert_eq!("Hello", response.into_string().unwrap());
+
+    // the flash message is kept for the next page
+    assert_eq!("success: Saved", client.get("/").dispatch().into_string().unwrap());
+}
+
+#[test]
+fn flash_from_disk_cache() {
+    let directory =
+        env::temp_dir().join(format!("rocket-include-tera-flash-{}", std::process::id()));
+
+    // every client is a restart, which empties the memory
+    let client = client_with(|fairing| fairing.disk_cache(&directory));
+
+    assert_eq!("Hello", client.get("/cached").dispatch().into_string().unwrap());
+
+    let client = client_with(|fairing| fairing.disk_cache(&directory));
+
+    client.get("/save?to=cached").dispatch();
+
+    if cfg!(debug_assertions) {
+        assert_eq!("success: Saved", client.get("/cached").dispatch().into_string().unwrap());
+    } else {
+        // the context is not kept on the disk, so the page of the disk cache leaves the flash message to the next page
+        assert_eq!("Hello", client.get("/cached").dispatch().into_string().unwrap());
+        assert_eq!("success: Saved", client.get("/").dispatch().into_string().unwrap());
+    }
+
+    fs::remove_dir_all(&directory).ok();
+}
@@ -0,0 +1,155 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+    State,
+};
+use rocket_include_tera::*;
+use serde_json::json;
+
+#[get("/")]
+fn index(
+    cm: &State<TeraContextManager>,
+    fragment_request: FragmentRequest,
+    etag_if_none_match: EtagIfNoneMatch,
+) -> TeraResponse {
+    tera_response!(
+        fragment fragment_request => "content",
+        cm,
+        etag_if_none_match,
+        "index",
+        json!({ "title": "Hello", "items": ["a", "b"] })
+    )
+}
+
+#[get("/sidebar")]
+fn sidebar(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    cm.build_block(
+        &etag_if_none_match,
+        false,
+        "index",
+        "sidebar",
+        json!({ "title": "Hello", "items": [] }),
+    )
+}
+
+#[get("/boosted")]
+fn boosted(
+    cm: &State<TeraContextManager>,
+    fragment_request: FragmentRequest,
+    etag_if_none_match: EtagIfNoneMatch,
+) -> TeraResponse {
+    tera_response!(fragment fragment_request => "content", cm, etag_if_none_match, "layout")
+}
+
+fn rocket(header: Option<&'static str>) -> rocket::Rocket<rocket::Build> {
+    let fairing = TeraResponse::fairing(|tera| {
+        tera.add_raw_templates(vec![
+            (
+                "layout",
+                "<html><aside>{% block sidebar %}Menu{% endblock %}</aside><main>{% block content \
+                 %}Empty{% endblock %}</main></html>",
+            ),
+            (
+                "index",
+                "{% extends \"layout\" %}{% block sidebar %}{{ super() }}: {{ title }}{% endblock \
+                 %}{% block content %}<h1>{{ title }}</h1><ul>{% for item in items %}<li>{{ item \
+                 }}</li>{% endfor %}</ul>{% endblock %}",
+            ),
+        ])
+        .unwrap();
+    });
+
+    let fairing = match header {
+        Some(header) => fairing.fragment_header(header),
+        None => fairing,
+    };
+
+    rocket::build().attach(fairing).mount("/", routes![index, sidebar, boosted])
+}
+
+#[test]
+fn fragment() {
+    let client = Client::tracked(rocket(None)).unwrap();
+
+    let response = client.get("/").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+
+    let page_etag = response.headers().get_one("ETag").unwrap().to_string();
+
+    assert_eq!(Some("HX-Request"), response.headers().get_one("Vary"));
+
+    assert_eq!(
+        "<html><aside>Menu: \
+         Hello</aside><main><h1>Hello</h1><ul><li>a</li><li>b</li></ul></main></html>",
+        response.into_string().unwrap()
+    );
+
+    let response = client.get("/").header(Header::new("HX-Request", "true")).dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+
+    let fragment_etag = response.headers().get_one("ETag").unwrap().to_string();
+
+    assert_eq!(Some("HX-Request"), response.headers().get_one("Vary"));
+
+    assert_ne!(page_etag, fragment_etag);
+    assert_eq!("<h1>Hello</h1><ul><li>a</li><li>b</li></ul>", response.into_string().unwrap());
+
+    let response = client
+        .get("/")
+        .header(Header::new("HX-Request", "true"))
+        .header(Header::new("If-None-Match", fragment_etag))
+        .dispatch();
+
+    assert_eq!(Status::NotModified, response.status());
+    assert_eq!(Some("HX-Request"), response.headers().get_one("Vary"));
+
+    let response = client
+        .get("/")
+        .header(Header::new("HX-Request", "true"))
+        .header(Header::new("If-None-Match", page_etag))
+        .dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+
+    let response = client.get("/sidebar").dispatch();
+
+    assert_eq!("Menu: Hello", response.into_string().unwrap());
+
+    let response = client.get("/boosted").header(Header::new("HX-Request", "true")).dispatch();
+
+    assert_eq!("Empty", response.into_string().unwrap());
+
+    let cm = client.rocket().state::<TeraContextManager>().unwrap();
+
+    #[cfg(debug_assertions)]
+    let tera = cm.tera.read().unwrap();
+    #[cfg(not(debug_assertions))]
+    let tera = &cm.tera;
+
+    // the templates of blocks are kept apart from the template set
+    let mut names = tera.get_template_names().collect::<Vec<_>>();
+
+    names.sort();
+
+    assert_eq!(vec!["index", "layout"], names);
+}
+
+#[test]
+fn fragment_header() {
+    let client = Client::tracked(rocket(Some("Turbo-Frame"))).unwrap();
+
+    let response = client.get("/").header(Header::new("HX-Request", "true")).dispatch();
+
+    assert!(response.into_string().unwrap().starts_with("<html>"));
+
+    let response = client.get("/").header(Header::new("Turbo-Frame", "main")).dispatch();
+
+    assert_eq!(Some("Turbo-Frame"), response.headers().get_one("Vary"));
+
+    assert_eq!("<h1>Hello</h1><ul><li>a</li><li>b</li></ul>", response.into_string().unwrap());
+}
@@ -0,0 +1,60 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{http::Status, local::blocking::Client, State};
+use rocket_include_tera::*;
+use serde_json::json;
+
+#[get("/")]
+fn index(
+    cm: &State<TeraContextManager>,
+    head_request: HeadRequest,
+    etag_if_none_match: EtagIfNoneMatch,
+) -> TeraResponse {
+    let context = json!({ "title": "Hello" });
+
+    tera_response!(head head_request => cm, etag_if_none_match, "index", context)
+}
+
+#[get("/cached")]
+fn cached(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response_cache!(cm, etag_if_none_match, "cached", {
+        tera_response!(cm, EtagIfNoneMatch::default(), "index", json!({ "title": "Hello" }))
+    })
+}
+
+#[test]
+fn head() {
+    let rocket = rocket::build()
+        .attach(TeraResponse::fairing(|tera| {
+            tera.add_raw_template("index", "<p>\n    {{ title }}\n</p>").unwrap();
+        }))
+        .mount("/", routes![index, cached]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    let response = client.get("/").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+
+    let etag = response.headers().get_one("ETag").unwrap().to_string();
+
+    let response = client.head("/").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!(Some(etag.as_str()), response.headers().get_one("ETag"));
+    assert_eq!(None, response.body().preset_size());
+    assert!(response.into_bytes().unwrap_or_default().is_empty());
+
+    let response = client.get("/cached").dispatch();
+
+    let etag = response.headers().get_one("ETag").unwrap().to_string();
+    let html = response.into_string().unwrap();
+
+    let response = client.head("/cached").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!(Some(etag.as_str()), response.headers().get_one("ETag"));
+    assert_eq!(Some(html.len()), response.body().preset_size());
+    assert!(response.into_bytes().unwrap_or_default().is_empty());
+}
@@ -0,0 +1,88 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+    State,
+};
+use rocket_include_tera::*;
+use serde_json::json;
+
+#[test]
+fn accept_language() {
+    let accept_language = AcceptLanguage::parse("en;q=0.5, de-CH, ja;q=0.8, *;q=0.1");
+
+    assert_eq!(["de-CH", "ja", "en"], &*accept_language);
+}
+
+#[test]
+fn localized() {
+    let rocket = rocket::build().attach(TeraResponse::fairing(|tera| {
+        tera_catalogs_initialize!(
+            tera,
+            "en" => "examples/locales/en.json",
+            "de" => "examples/locales/de.json",
+        );
+
+        tera.add_raw_template("page", r#"{{ t(key="greeting", name=name) }}"#).unwrap();
+        tera.add_raw_template("page.ja", "こんにちは").unwrap();
+        tera.add_raw_template("nav", r#"{{ t(key="nav.home", lang="de") }}"#).unwrap();
+    }));
+
+    let client = Client::tracked(rocket).unwrap();
+
+    let cm = client.rocket().state::<TeraContextManager>().unwrap();
+
+    let context = json!({ "name": "Len" });
+
+    assert_eq!("Hallo, Len!", cm.render_localized(&["de-CH", "en"], "page", &context));
+    assert_eq!("Hello, Len!", cm.render_localized(&["fr", "en"], "page", &context));
+    assert_eq!("こんにちは", cm.render_localized(&["ja"], "page", &context));
+    assert_eq!("greeting", cm.render("page", &context));
+    assert_eq!("Startseite", cm.render("nav", &context));
+}
+
+#[get("/")]
+fn index(
+    cm: &State<TeraContextManager>,
+    etag_if_none_match: EtagIfNoneMatch,
+    accept_language: AcceptLanguage,
+) -> TeraResponse {
+    cm.build_localized(
+        &accept_language,
+        &etag_if_none_match,
+        false,
+        "page",
+        json!({ "name": "Len" }),
+    )
+}
+
+#[test]
+fn localized_response() {
+    let rocket = rocket::build()
+        .attach(TeraResponse::fairing(|tera| {
+            tera_catalogs_initialize!(
+                tera,
+                "en" => "examples/locales/en.json",
+                "DE_de" => "examples/locales/de.json",
+            );
+
+            tera.add_raw_template("page", r#"{{ t(key="greeting", name=name) }}"#).unwrap();
+            tera.add_raw_template("page.zh-Hant", "你好").unwrap();
+        }))
+        .mount("/", routes![index]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    for (accept_language, body) in
+        [("de-de", "Hallo, Len!"), ("zh_HANT", "你好"), ("fr, en;q=0.5", "Hello, Len!")]
+    {
+        let response =
+            client.get("/").header(Header::new("Accept-Language", accept_language)).dispatch();
+
+        assert_eq!(Status::Ok, response.status());
+        assert_eq!(Some("Accept-Language"), response.headers().get_one("Vary"));
+        assert_eq!(Some(body.to_string()), response.into_string());
+    }
+}
@@ -0,0 +1,108 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+    State,
+};
+use rocket_include_tera::*;
+use serde_json::json;
+
+#[get("/")]
+fn index(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    let context = json!({ "title": "Hello", "id": 0, "placeholder": "Name" });
+
+    tera_response_cache!(cm, etag_if_none_match, "index2", {
+        tera_response!(cm, EtagIfNoneMatch::default(), "index2", context)
+    })
+}
+
+#[get("/guarded")]
+fn guarded(cm: &State<TeraContextManager>, if_modified_since: IfModifiedSince) -> TeraResponse {
+    match cm.last_modified("index2") {
+        Some(last_modified) if !if_modified_since.is_modified(last_modified) => {
+            TeraResponse::not_modified()
+        },
+        _ => panic!("the template should not be rendered"),
+    }
+}
+
+#[test]
+fn last_modified() {
+    let rocket = rocket::build()
+        .attach(TeraResponse::fairing(|tera| {
+            tera_resources_initialize!(tera, "index2" => "examples/views/index2.tera");
+        }))
+        .mount("/", routes![index, guarded]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    let response = client.get("/").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+
+    let last_modified = response.headers().get_one("Last-Modified").unwrap().to_string();
+
+    let response =
+        client.get("/").header(Header::new("If-Modified-Since", last_modified.clone())).dispatch();
+
+    assert_eq!(Status::NotModified, response.status());
+
+    let response = client
+        .get("/")
+        .header(Header::new("If-Modified-Since", "Thu, 01 Jan 1970 00:00:00 GMT"))
+        .dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+
+    // If-Modified-Since is ignored when If-None-Match is given
+    let response = client
+        .get("/")
+        .header(Header::new("If-Modified-Since", last_modified.clone()))
+        .header(Header::new("If-None-Match", "\"mismatch\""))
+        .dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+
+    let response =
+        client.get("/guarded").header(Header::new("If-Modified-Since", last_modified)).dispatch();
+
+    assert_eq!(Status::NotModified, response.status());
+}
+
+#[test]
+fn remove_last_modified() {
+    let rocket = rocket::build()
+        .attach(TeraResponse::fairing(|tera| {
+            tera.add_raw_template("index", "<p>{{ title }}</p>").unwrap();
+
+            tera_resources_initialize!(tera, "index2" => "examples/views/index2.tera");
+        }))
+        .mount("/", routes![raw, plain]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    let response = client.get("/").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!(None, response.headers().get_one("Last-Modified"));
+
+    // only responses of the cache have the timestamp by default
+    let response = client.get("/plain").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!(None, response.headers().get_one("Last-Modified"));
+}
+
+#[get("/")]
+fn raw(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response!(cm, etag_if_none_match, "index", json!({ "title": "Hello" })).last_modified(None)
+}
+
+#[get("/plain")]
+fn plain(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    let context = json!({ "title": "Hello", "id": 0, "placeholder": "Name" });
+
+    tera_response!(cm, etag_if_none_match, "index2", context)
+}
@@ -0,0 +1,83 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Accept, ContentType, Header, Status},
+    local::blocking::Client,
+    State,
+};
+use rocket_include_tera::*;
+use serde_json::json;
+
+#[get("/")]
+fn index(
+    cm: &State<TeraContextManager>,
+    format: ResponseFormat,
+    etag_if_none_match: EtagIfNoneMatch,
+) -> TeraResponse {
+    cm.build_negotiated(format, &etag_if_none_match, true, "index", json!({ "title": "Hello" }))
+}
+
+#[get("/json")]
+fn json(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response_cache!(cm, etag_if_none_match, "json", {
+        cm.build_negotiated(
+            ResponseFormat::Json,
+            &EtagIfNoneMatch::default(),
+            true,
+            "index",
+            json!({ "title": "Hello" }),
+        )
+    })
+}
+
+#[test]
+fn negotiation() {
+    let rocket = rocket::build()
+        .attach(TeraResponse::fairing(|tera| {
+            tera.add_raw_template("index", "<h1>{{ title }}</h1>").unwrap();
+        }))
+        .mount("/", routes![index, json]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    let response = client.get("/").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!(Some(ContentType::HTML), response.content_type());
+    assert_eq!(Some("Accept"), response.headers().get_one("Vary"));
+    assert_eq!("<h1>Hello</h1>", response.into_string().unwrap());
+
+    let response = client
+        .get("/")
+        .header(Header::new("Accept", "text/html,application/xhtml+xml,*/*;q=0.8"))
+        .dispatch();
+
+    assert_eq!(Some(ContentType::HTML), response.content_type());
+
+    let response = client.get("/").header(Accept::JSON).dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!(Some(ContentType::JSON), response.content_type());
+    assert_eq!(Some("Accept"), response.headers().get_one("Vary"));
+
+    let etag = response.headers().get_one("ETag").unwrap().to_string();
+
+    assert_eq!(r#"{"title":"Hello"}"#, response.into_string().unwrap());
+
+    let response =
+        client.get("/").header(Accept::JSON).header(Header::new("If-None-Match", etag)).dispatch();
+
+    assert_eq!(Status::NotModified, response.status());
+
+    // JSON is never stored in the cache, which only holds HTML
+    for _ in 0..2 {
+        let response = client.get("/json").dispatch();
+
+        assert_eq!(Some(ContentType::JSON), response.content_type());
+    }
+
+    let cm = client.rocket().state::<TeraContextManager>().unwrap();
+
+    assert!(cm.cache_keys().is_empty());
+}
@@ -0,0 +1,63 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{http::Status, local::blocking::Client, State};
+use rocket_include_tera::*;
+use serde_json::json;
+
+#[get("/<theme>")]
+fn index(
+    cm: &State<TeraContextManager>,
+    etag_if_none_match: EtagIfNoneMatch,
+    theme: &str,
+) -> TeraResponse {
+    cm.build_overlay(theme, &etag_if_none_match, false, "index", json!({ "title": "Hello" }))
+}
+
+#[test]
+fn overlay() {
+    let rocket = rocket::build()
+        .attach(
+            TeraResponse::fairing(|tera| {
+                tera.add_raw_templates(vec![
+                    (
+                        "base",
+                        "<body>{% block content %}{% endblock %}{% include \"footer\" %}</body>",
+                    ),
+                    ("footer", "<footer>Base</footer>"),
+                    (
+                        "index",
+                        "{% extends \"base\" %}{% block content %}<h1>{{ title }}</h1>{% endblock \
+                         %}",
+                    ),
+                ])
+                .unwrap();
+            })
+            .overlay("dark", |tera| {
+                tera.add_raw_templates(vec![
+                    (
+                        "base",
+                        "<body class=\"dark\">{% block content %}{% endblock %}{% include \
+                         \"footer\" %}</body>",
+                    ),
+                    ("footer", "<footer>Dark</footer>"),
+                ])
+                .unwrap();
+            }),
+        )
+        .mount("/", routes![index]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    let response = client.get("/dark").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!(
+        "<body class=\"dark\"><h1>Hello</h1><footer>Dark</footer></body>",
+        response.into_string().unwrap()
+    );
+
+    let response = client.get("/unknown").dispatch();
+
+    assert_eq!(Status::InternalServerError, response.status());
+}
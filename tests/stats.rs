@@ -0,0 +1,112 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{http::Status, local::blocking::Client, State};
+use rocket_include_tera::*;
+use serde_json::json;
+
+#[get("/<id>")]
+fn page(
+    cm: &State<TeraContextManager>,
+    etag_if_none_match: EtagIfNoneMatch,
+    id: usize,
+) -> TeraResponse {
+    tera_response_cache!(cm, etag_if_none_match, format!("page-{}", id), {
+        tera_response!(cm, EtagIfNoneMatch::default(), "page", json!({ "id": id }))
+    })
+}
+
+#[test]
+fn stats() {
+    let rocket = rocket::build()
+        .attach(
+            TeraResponse::fairing_cache(|tera| {
+                tera.add_raw_template("page", "<p>{{ id }}</p>").unwrap();
+
+                2
+            })
+            .cache_shards(1),
+        )
+        .mount("/", routes![page])
+        .mount("/admin/tera-cache", TeraCacheStats::new());
+
+    let client = Client::tracked(rocket).unwrap();
+
+    for id in [1, 1, 2, 3] {
+        let response = client.get(format!("/{}", id)).dispatch();
+
+        assert_eq!(Status::Ok, response.status());
+    }
+
+    let cm = client.rocket().state::<TeraContextManager>().unwrap();
+
+    let stats = cm.cache_stats();
+
+    let response = client.get("/admin/tera-cache").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!(
+        serde_json::to_value(stats).unwrap(),
+        serde_json::from_str::<serde_json::Value>(&response.into_string().unwrap()).unwrap()
+    );
+
+    if cfg!(debug_assertions) {
+        assert_eq!(CacheStats::default(), stats);
+        assert!(cm.cache_keys().is_empty());
+    } else {
+        assert_eq!(
+            CacheStats {
+                hits:      1,
+                misses:    3,
+                inserts:   3,
+                evictions: 1,
+                entries:   2,
+                bytes:     "<p>2</p>".len() + "<p>3</p>".len(),
+            },
+            stats
+        );
+
+        let mut keys = cm.cache_keys();
+
+        keys.sort();
+
+        assert_eq!(vec!["page-2", "page-3"], keys);
+    }
+}
+
+#[test]
+fn cache_bytes() {
+    // the context is kept to render the page with flash messages, so it is weighed as well
+    let weight = "<p>1</p>".len()
+        + EntityTag::from_data("<p>1</p>").get_tag().len()
+        + json!({ "id": 1 }).to_string().len();
+
+    // the capacity is replaced by the budget
+    let rocket = rocket::build()
+        .attach(
+            TeraResponse::fairing_cache(|tera| {
+                tera.add_raw_template("page", "<p>{{ id }}</p>").unwrap();
+
+                1
+            })
+            .cache_bytes(weight * 2 + 1),
+        )
+        .mount("/", routes![page]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    for id in [1, 2, 3] {
+        let response = client.get(format!("/{}", id)).dispatch();
+
+        assert_eq!(Status::Ok, response.status());
+    }
+
+    let stats = client.rocket().state::<TeraContextManager>().unwrap().cache_stats();
+
+    if cfg!(debug_assertions) {
+        assert_eq!(CacheStats::default(), stats);
+    } else {
+        assert_eq!(1, stats.evictions);
+        assert_eq!(2, stats.entries);
+    }
+}
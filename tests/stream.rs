@@ -0,0 +1,113 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+    State,
+};
+use rocket_include_tera::*;
+use serde_json::json;
+
+const ROWS: usize = 10000;
+
+#[get("/")]
+fn index(cm: &State<TeraContextManager>) -> TeraResponse {
+    cm.build_stream("rows", json!({ "rows": ROWS }))
+}
+
+#[get("/etag")]
+fn etag(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    cm.build_stream_with_context_etag(&etag_if_none_match, "rows", json!({ "rows": ROWS }))
+}
+
+#[get("/broken")]
+fn broken(cm: &State<TeraContextManager>) -> TeraResponse {
+    cm.build_stream("broken", json!({ "rows": ROWS }))
+}
+
+#[get("/unknown")]
+fn unknown(cm: &State<TeraContextManager>) -> TeraResponse {
+    cm.build_stream("unknown", json!({}))
+}
+
+#[test]
+fn stream() {
+    let rocket = rocket::build()
+        .attach(TeraResponse::fairing(|tera| {
+            tera.add_raw_template(
+                "rows",
+                "{% for i in range(end=rows) %}<p>{{ i }}</p>{% endfor %}",
+            )
+            .unwrap();
+
+            tera.add_raw_template(
+                "broken",
+                "{% for i in range(end=rows) %}<p>{{ i }}</p>{% endfor %}{{ missing }}",
+            )
+            .unwrap();
+        }))
+        .mount("/", routes![index, etag, broken, unknown]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    let expected = (0..ROWS).map(|i| format!("<p>{}</p>", i)).collect::<String>();
+
+    let response = client.get("/").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!(None, response.headers().get_one("ETag"));
+    assert_eq!(None, response.body().preset_size());
+    assert_eq!(expected, response.into_string().unwrap());
+
+    let response = client.get("/etag").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+
+    let etag = response.headers().get_one("ETag").unwrap().to_string();
+
+    assert_eq!(expected, response.into_string().unwrap());
+
+    let response = client.get("/etag").header(Header::new("If-None-Match", etag)).dispatch();
+
+    assert_eq!(Status::NotModified, response.status());
+
+    // the body fails instead of ending like a complete page
+    let response = client.get("/broken").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!(None, response.into_string());
+
+    let response = client.get("/unknown").dispatch();
+
+    assert_eq!(Status::InternalServerError, response.status());
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn stream_does_not_block_reloading() {
+    use std::{thread, time::Duration};
+
+    let rocket = rocket::build()
+        .attach(TeraResponse::fairing(|tera| {
+            tera.add_raw_template(
+                "rows",
+                "{% for i in range(end=rows) %}<p>{{ i }}</p>{% endfor %}",
+            )
+            .unwrap();
+        }))
+        .mount("/", routes![index]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    // the body is not read, so the renderer waits for the client
+    let response = client.get("/").dispatch();
+
+    thread::sleep(Duration::from_millis(100));
+
+    let cm = client.rocket().state::<TeraContextManager>().unwrap();
+
+    assert!(cm.tera.try_write().is_ok());
+
+    drop(response);
+}
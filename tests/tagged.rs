@@ -0,0 +1,50 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{http::Status, local::blocking::Client, State};
+use rocket_include_tera::*;
+use serde_json::json;
+
+struct Admin;
+
+#[get("/")]
+fn index(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response!(cm, etag_if_none_match, "page", json!({ "title": "Site" }))
+}
+
+#[get("/admin")]
+fn admin(
+    cm: &State<TeraContextManager<Admin>>,
+    etag_if_none_match: EtagIfNoneMatch,
+) -> TeraResponse {
+    tera_response!(cm, etag_if_none_match, "page", json!({ "title": "Admin" }))
+}
+
+#[test]
+fn tagged() {
+    let rocket = rocket::build()
+        .attach(TeraResponse::fairing(|tera| {
+            tera.add_raw_template("page", "<p>Public: {{ title }}</p>").unwrap();
+        }))
+        .attach(
+            TeraResponse::fairing_cache(|tera| {
+                tera.add_raw_template("page", "<p>Admin panel: {{ title }}</p>").unwrap();
+
+                16
+            })
+            .tagged::<Admin>(),
+        )
+        .mount("/", routes![index, admin]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    let response = client.get("/").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("<p>Public: Site</p>", response.into_string().unwrap());
+
+    let response = client.get("/admin").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert_eq!("<p>Admin panel: Admin</p>", response.into_string().unwrap());
+}
@@ -0,0 +1,64 @@
+#[macro_use]
+extern crate rocket;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rocket::{error::ErrorKind, http::Status, local::blocking::Client, State};
+use rocket_include_tera::*;
+use serde_json::json;
+
+static GENERATED: AtomicUsize = AtomicUsize::new(0);
+
+fn context() -> serde_json::Value {
+    json!({
+        "title": "Title",
+        "placeholder": "Hello",
+        "id": 0,
+    })
+}
+
+#[get("/")]
+fn index(cm: &State<TeraContextManager>, etag_if_none_match: EtagIfNoneMatch) -> TeraResponse {
+    tera_response_cache!(cm, etag_if_none_match, "index-2", {
+        GENERATED.fetch_add(1, Ordering::SeqCst);
+
+        tera_response!(cm, EtagIfNoneMatch::default(), "index2", context())
+    })
+}
+
+#[test]
+fn warm() {
+    let rocket = rocket::build()
+        .attach(
+            tera_resources_initializer!(
+                "index2" => "examples/views/index2.tera"
+            )
+            .warm("index-2", "index2", context),
+        )
+        .mount("/", routes![index]);
+
+    let client = Client::tracked(rocket).unwrap();
+
+    let response = client.get("/").dispatch();
+
+    assert_eq!(Status::Ok, response.status());
+    assert!(response.into_string().unwrap().contains("input-1"));
+
+    let expected = if cfg!(debug_assertions) { 1 } else { 0 };
+
+    assert_eq!(expected, GENERATED.load(Ordering::SeqCst));
+}
+
+#[test]
+fn warm_failure() {
+    let rocket = rocket::build().attach(
+        tera_resources_initializer!(
+            "index2" => "examples/views/index2.tera"
+        )
+        .warm("missing", "missing", || json!({})),
+    );
+
+    let err = rocket::execute(rocket.ignite()).unwrap_err();
+
+    assert!(matches!(err.kind(), ErrorKind::FailedFairings(_)));
+}